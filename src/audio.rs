@@ -0,0 +1,3 @@
+pub mod backend;
+pub mod mixer;
+pub mod wav;
@@ -0,0 +1,48 @@
+//! Where the mixed samples go: nowhere, OR a .wav file.
+
+use crate::audio::wav::Sound;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub trait Backend {
+    // mono, at the rate of the mixer
+    fn write(&mut self, samples: &[f32]);
+}
+
+// no sound device, samples are dropped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Null;
+
+// offline, everything is kept until save()
+#[derive(Debug, Clone)]
+pub struct WavWriter {
+    pub path: PathBuf,
+    pub sound: Sound,
+}
+
+////////////////////////////////////////
+impl Backend for Null {
+    fn write(&mut self, _: &[f32]) {}
+}
+
+impl WavWriter {
+    pub fn new(path: &Path, rate: u32) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            sound: Sound {
+                samples: vec![],
+                rate,
+            },
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.sound.save(&self.path)
+    }
+}
+
+impl Backend for WavWriter {
+    fn write(&mut self, samples: &[f32]) {
+        self.sound.samples.extend_from_slice(samples);
+    }
+}
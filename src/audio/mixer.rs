@@ -0,0 +1,276 @@
+//! Mixer: sound effects, looping music and volume groups.
+
+use crate::{
+    audio::{backend::Backend, wav::Sound},
+    FPS,
+};
+use std::fs;
+use std::path::Path;
+
+pub const RATE: u32 = 44100;
+
+// playing at the same time, the oldest effect goes first
+const VOICES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Music,
+    Sfx,
+    Voice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Voice {
+    sound: usize,
+    pos: f32, // in samples of the sound
+    step: f32,
+    group: Group,
+    looping: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    sounds: Vec<(String, Sound)>,
+    voices: Vec<Voice>,
+
+    pub master: f32,
+    volumes: [f32; 3], // by Group
+    rate: u32,
+
+    ticks: u64,   // since new()
+    written: u64, // samples
+}
+
+////////////////////////////////////////
+impl Mixer {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            sounds: vec![],
+            voices: vec![],
+            master: 1.0,
+            volumes: [0.6, 1.0, 1.0],
+            rate,
+            ticks: 0,
+            written: 0,
+        }
+    }
+
+    // {dir}/*.wav, named by the file stem
+    pub fn load(dir: &Path, rate: u32) -> Self {
+        let mut mixer = Self::new(rate);
+
+        for path in fs::read_dir(dir).unwrap().flatten().map(|e| e.path()) {
+            if path.extension().is_none_or(|ext| ext != "wav") {
+                continue;
+            }
+
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+
+            match Sound::open(&path) {
+                Some(sound) => mixer.add(&name, sound),
+                None => log::warn!("{}: not a PCM .wav", path.display()),
+            }
+        }
+
+        log::debug!("Mixer: {} sounds", mixer.sounds.len());
+
+        mixer
+    }
+
+    pub fn add(&mut self, name: &str, sound: Sound) {
+        self.sounds.push((name.to_string(), sound));
+    }
+
+    #[inline(always)]
+    pub fn volume(&self, group: Group) -> f32 {
+        self.volumes[group as usize]
+    }
+
+    pub fn set_volume(&mut self, group: Group, volume: f32) {
+        self.volumes[group as usize] = volume.clamp(0.0, 1.0);
+    }
+
+    // false: no such sound
+    pub fn play(&mut self, name: &str, group: Group) -> bool {
+        self.start(name, group, false)
+    }
+
+    // replaces the music playing
+    pub fn play_music(&mut self, name: &str) -> bool {
+        self.stop(Group::Music);
+        self.start(name, Group::Music, true)
+    }
+
+    pub fn stop(&mut self, group: Group) {
+        self.voices.retain(|v| v.group != group);
+    }
+
+    #[inline(always)]
+    pub fn playing(&self) -> usize {
+        self.voices.len()
+    }
+
+    fn start(&mut self, name: &str, group: Group, looping: bool) -> bool {
+        let Some(sound) = self.sounds.iter().position(|(n, _)| n == name) else {
+            log::debug!("Mixer: no sound `{}`", name);
+            return false;
+        };
+
+        if self.voices.len() >= VOICES {
+            if let Some(idx) = self.voices.iter().position(|v| !v.looping) {
+                self.voices.remove(idx);
+            } else {
+                return false;
+            }
+        }
+
+        self.voices.push(Voice {
+            sound,
+            pos: 0.0,
+            step: self.sounds[sound].1.rate as f32 / self.rate as f32,
+            group,
+            looping,
+        });
+
+        true
+    }
+
+    // adds all the voices to `out`, clipped
+    pub fn mix(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+
+        for voice in self.voices.iter_mut() {
+            let samples = &self.sounds[voice.sound].1.samples;
+            let volume = self.master * self.volumes[voice.group as usize];
+
+            if samples.is_empty() {
+                voice.looping = false;
+                voice.pos = 1.0;
+                continue;
+            }
+
+            for s in out.iter_mut() {
+                let mut idx = voice.pos as usize;
+
+                if idx >= samples.len() {
+                    if !voice.looping {
+                        break;
+                    }
+
+                    voice.pos -= samples.len() as f32;
+                    idx = voice.pos as usize % samples.len();
+                }
+
+                *s += samples[idx] * volume;
+                voice.pos += voice.step;
+            }
+        }
+
+        for s in out.iter_mut() {
+            *s = s.clamp(-1.0, 1.0);
+        }
+
+        let sounds = &self.sounds;
+        self.voices
+            .retain(|v| v.looping || (v.pos as usize) < sounds[v.sound].1.samples.len());
+    }
+
+    // the samples of one tick, to the backend
+    pub fn tick(&mut self, backend: &mut dyn Backend) {
+        self.ticks += 1;
+
+        let due = self.ticks * self.rate as u64 * FPS as u64 / 1000;
+        let mut out = vec![0.0; (due - self.written) as usize];
+
+        self.mix(&mut out);
+        self.written = due;
+
+        backend.write(&out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::WavWriter;
+
+    fn mixer(samples: Vec<f32>) -> Mixer {
+        let mut mixer = Mixer::new(RATE);
+        mixer.set_volume(Group::Music, 1.0);
+        mixer.add(
+            "beep",
+            Sound {
+                samples,
+                rate: RATE,
+            },
+        );
+        mixer
+    }
+
+    #[test]
+    fn tick_writes_the_samples_of_a_tick() {
+        let mut mixer = mixer(vec![0.5; 100]);
+        let mut writer = WavWriter::new(Path::new("unused.wav"), RATE);
+
+        for ticks in 1..=240u64 {
+            mixer.tick(&mut writer);
+
+            let due = ticks * RATE as u64 * FPS as u64 / 1000;
+            assert_eq!(writer.sound.samples.len() as u64, due);
+        }
+    }
+
+    #[test]
+    fn music_loops() {
+        let mut mixer = mixer(vec![0.1, 0.2, 0.3, 0.4]);
+        assert!(mixer.play_music("beep"));
+
+        let mut out = [0.0; 10];
+        mixer.mix(&mut out);
+
+        assert_eq!(out, [0.1, 0.2, 0.3, 0.4, 0.1, 0.2, 0.3, 0.4, 0.1, 0.2]);
+        assert_eq!(mixer.playing(), 1);
+    }
+
+    #[test]
+    fn effects_end() {
+        let mut mixer = mixer(vec![0.1, 0.2]);
+        assert!(mixer.play("beep", Group::Sfx));
+
+        let mut out = [0.0; 4];
+        mixer.mix(&mut out);
+
+        assert_eq!(out, [0.1, 0.2, 0.0, 0.0]);
+        assert_eq!(mixer.playing(), 0);
+    }
+
+    #[test]
+    fn oldest_effect_is_evicted() {
+        let mut mixer = mixer(vec![0.1; 1000]);
+        assert!(mixer.play_music("beep"));
+
+        for _ in 1..VOICES {
+            assert!(mixer.play("beep", Group::Sfx));
+        }
+        mixer.voices[1].pos = 7.0; // the oldest effect
+
+        assert!(mixer.play("beep", Group::Voice));
+        assert_eq!(mixer.playing(), VOICES);
+        assert!(mixer.voices[0].looping);
+        assert!(mixer.voices.iter().all(|v| v.pos == 0.0));
+        assert_eq!(mixer.voices.last().unwrap().group, Group::Voice);
+    }
+
+    #[test]
+    fn music_is_not_evicted() {
+        let mut mixer = mixer(vec![0.1; 1000]);
+
+        for _ in 0..VOICES {
+            assert!(mixer.play_music("beep"));
+            mixer.voices.last_mut().unwrap().group = Group::Sfx;
+        }
+
+        assert!(!mixer.play("beep", Group::Sfx));
+        assert_eq!(mixer.playing(), VOICES);
+    }
+}
@@ -0,0 +1,126 @@
+//! PCM .wav files: 8 or 16 bit in, 16 bit mono out.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// mono, -1.0 ..= 1.0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sound {
+    pub samples: Vec<f32>,
+    pub rate: u32,
+}
+
+////////////////////////////////////////
+impl Sound {
+    // None: not a PCM .wav
+    pub fn open(path: &Path) -> Option<Self> {
+        Self::parse(&fs::read(path).ok()?)
+    }
+
+    // channels are mixed down
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return None;
+        }
+
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+        let mut fmt = None; // channels, rate, bits
+        let mut data: &[u8] = &[];
+        let mut at = 12;
+
+        while at + 8 <= bytes.len() {
+            let len = u32_at(at + 4) as usize;
+            let body = &bytes[at + 8..(at + 8 + len).min(bytes.len())];
+
+            match &bytes[at..at + 4] {
+                b"fmt " if body.len() >= 16 && u16_at(at + 8) == 1 => {
+                    fmt = Some((u16_at(at + 10), u32_at(at + 12), u16_at(at + 22)));
+                }
+                b"data" => data = body,
+                _ => {}
+            }
+
+            // chunks are padded to an even size
+            at += 8 + len + (len & 1);
+        }
+
+        let (channels, rate, bits) = fmt?;
+        let channels = channels.max(1) as usize;
+
+        let raw: Vec<f32> = match bits {
+            8 => data.iter().map(|b| (*b as f32 - 128.0) / 128.0).collect(),
+            16 => data
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                .collect(),
+            _ => return None,
+        };
+
+        let samples = raw
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        Some(Self { samples, rate })
+    }
+
+    // 16 bit mono
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = self.samples.len() as u32 * 2;
+        let mut res = Vec::with_capacity(44 + len as usize);
+
+        res.extend_from_slice(b"RIFF");
+        res.extend_from_slice(&(36 + len).to_le_bytes());
+        res.extend_from_slice(b"WAVEfmt ");
+        res.extend_from_slice(&16u32.to_le_bytes());
+        res.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        res.extend_from_slice(&1u16.to_le_bytes()); // mono
+        res.extend_from_slice(&self.rate.to_le_bytes());
+        res.extend_from_slice(&(self.rate * 2).to_le_bytes()); // bytes per second
+        res.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+        res.extend_from_slice(&16u16.to_le_bytes());
+        res.extend_from_slice(b"data");
+        res.extend_from_slice(&len.to_le_bytes());
+
+        for s in self.samples.iter() {
+            let v = (s.clamp(-1.0, 1.0) * 32767.0) as i16;
+            res.extend_from_slice(&v.to_le_bytes());
+        }
+
+        res
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let sound = Sound {
+            samples: vec![0.0, 0.5, -0.5, 1.0, -1.0, 0.25],
+            rate: 22050,
+        };
+        let back = Sound::parse(&sound.to_bytes()).unwrap();
+
+        assert_eq!(back.rate, sound.rate);
+        assert_eq!(back.samples.len(), sound.samples.len());
+
+        for (a, b) in back.samples.iter().zip(sound.samples.iter()) {
+            assert!((a - b).abs() < 1.0 / 16384.0, "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn not_a_wav() {
+        assert_eq!(Sound::parse(b"RIFF"), None);
+        assert_eq!(Sound::parse(&[0; 64]), None);
+    }
+}
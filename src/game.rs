@@ -0,0 +1,8 @@
+pub mod combat;
+pub mod combo;
+pub mod entity;
+pub mod knockdown;
+pub mod round;
+pub mod sim;
+pub mod state;
+pub mod training;
@@ -0,0 +1,418 @@
+//! Attacks, hit detection, hitstun and blockstun.
+
+use crate::{
+    game::{
+        combo::Combo,
+        entity::Entity,
+        knockdown::{self, Bounce, Knockdown},
+        state::State,
+    },
+    res::chara::{Bonus, CharData, Input},
+    res::sprite::{Dire, Player, Status},
+};
+
+// frame data, in ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attack {
+    pub startup: u32,
+    pub active: u32,
+    pub recovery: u32,
+
+    pub damage: u32,
+    pub hitstun: u32,
+    pub blockstun: u32,
+    pub knockdown: Knockdown,
+    pub launch: (i32, i32), // px per tick: back, up
+    pub juggle: u32,        // points, in the air
+    pub bounce: Bounce,
+    pub throw: u32,   // range of the throw box, 0: a strike
+    pub hitstop: u32, // both players freeze on contact
+
+    pub invincible: Invincible,
+    pub armor: Option<Armor>,
+}
+
+// frames of the move, from..to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Invincible {
+    pub strike: Option<(u32, u32)>,
+    pub throw: Option<(u32, u32)>,
+    pub projectile: Option<(u32, u32)>,
+}
+
+// absorbs hits during its frames, the damage is still taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Armor {
+    pub hits: u32,
+    pub from: u32,
+    pub to: u32,
+}
+
+// Counter: in the startup of the defender, Punish: in its recovery
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HitKind {
+    #[default]
+    Normal,
+    Counter,
+    Punish,
+    Armor,
+}
+
+// in progress, the defender can tech during the first ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throw {
+    pub attacker: usize, // 0: p1, 1: p2
+    pub timer: u32,
+    pub attack: Attack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub attacker: usize, // 0: p1, 1: p2
+    pub blocked: bool,
+    pub damage: u32,
+    pub kind: HitKind,
+    pub at: (u32, u32), // contact point, stage coordinates: x, height
+}
+
+pub const JAB: Attack = Attack {
+    startup: 4,
+    active: 3,
+    recovery: 8,
+    damage: 50,
+    hitstun: 14,
+    blockstun: 10,
+    knockdown: Knockdown::None,
+    launch: (0, 0),
+    juggle: 1,
+    bounce: Bounce::None,
+    throw: 0,
+    hitstop: 6,
+    invincible: Invincible {
+        strike: None,
+        throw: None,
+        projectile: None,
+    },
+    armor: None,
+};
+
+// in ticks
+pub const TECH_WINDOW: u32 = 12;
+pub const THROW_TIME: u32 = 40;
+const TECH_STUN: u32 = 16;
+
+// px
+const THROW_GAP: u32 = 120; // between the centers, while throwing
+const THROW_LIFT: u32 = 80;
+const TECH_PUSH: u32 = 40;
+
+////////////////////////////////////////
+impl Attack {
+    // the first active frame is counted in startup
+    #[inline(always)]
+    pub fn total(&self) -> u32 {
+        self.startup.saturating_sub(1) + self.active + self.recovery
+    }
+
+    // frame 0 is the first frame of the attack
+    #[inline(always)]
+    pub fn is_active(&self, frame: u32) -> bool {
+        frame + 1 >= self.startup && frame + 1 < self.startup + self.active
+    }
+
+    #[inline(always)]
+    pub fn is_startup(&self, frame: u32) -> bool {
+        frame + 1 < self.startup
+    }
+
+    #[inline(always)]
+    pub fn is_recovery(&self, frame: u32) -> bool {
+        frame + 1 >= self.startup + self.active
+    }
+}
+
+impl Default for Attack {
+    fn default() -> Self {
+        JAB
+    }
+}
+
+////////////////////////////////////////
+// combo: of the attacker
+pub fn check_hit(attacker: &mut Player, defender: &mut Player, combo: &mut Combo) -> Option<Hit> {
+    // no hits on the ground OR in a throw
+    if attacker.hit_done
+        || !attacker.is_active()
+        || !is_hittable(defender)
+        || !knockdown::can_juggle(defender, &attacker.attack)
+        || in_frames(defender, defender.attack.invincible.strike)
+    {
+        return None;
+    }
+
+    let hit = attacker.hitboxes().hit;
+    let hurt = defender.hitboxes().hurt;
+
+    // no overlap, no hit
+    let at = hit
+        .iter()
+        .find_map(|a| hurt.iter().find(|b| a.overlap(b)).map(|b| a.contact(b)))?;
+
+    let attack = attacker.attack;
+    let blocked = defender.is_guarding(attacker);
+
+    let id = if attacker.is_p1 { 0 } else { 1 };
+
+    attacker.hit_done = true;
+    attacker.hitstop = attack.hitstop;
+
+    if !blocked && is_armored(defender) {
+        return Some(Hit {
+            at,
+            ..absorb(id, defender, &attack)
+        });
+    }
+
+    // by the state of the defender, before the hit
+    let kind = if blocked {
+        HitKind::Normal
+    } else {
+        hit_kind(defender)
+    };
+    let attack = with_bonus(&attacker.chara, kind, attack);
+
+    Some(Hit {
+        kind,
+        at,
+        ..apply_hit(id, defender, &attack, blocked, combo)
+    })
+}
+
+// a projectile, destroyed on hit
+pub fn check_entity_hit(
+    entity: &mut Entity,
+    defender: &mut Player,
+    combo: &mut Combo,
+) -> Option<Hit> {
+    if !entity.is_alive()
+        || !is_hittable(defender)
+        || !knockdown::can_juggle(defender, &entity.attack)
+        || in_frames(defender, defender.attack.invincible.projectile)
+    {
+        return None;
+    }
+
+    let hit = entity.hitbox();
+
+    let at = defender
+        .hitboxes()
+        .hurt
+        .iter()
+        .find(|b| hit.overlap(b))
+        .map(|b| hit.contact(b))?;
+
+    let blocked = defender.is_guarding_from(entity.x.max(0) as u32);
+    entity.hp = 0;
+
+    if !blocked && is_armored(defender) {
+        return Some(Hit {
+            at,
+            ..absorb(entity.owner, defender, &entity.attack)
+        });
+    }
+
+    // no counter hits, the owner may not be attacking anymore
+    Some(Hit {
+        at,
+        ..apply_hit(entity.owner, defender, &entity.attack, blocked, combo)
+    })
+}
+
+// stun and damage, scaled by the combo of the attacker
+pub fn apply_hit(
+    attacker: usize,
+    defender: &mut Player,
+    attack: &Attack,
+    blocked: bool,
+    combo: &mut Combo,
+) -> Hit {
+    let damage = combo.scale(attack.damage);
+    defender.hitstop = attack.hitstop;
+
+    if blocked {
+        defender.status = Status::Guard(attack.blockstun);
+    } else {
+        defender.hp = defender.hp.saturating_sub(damage);
+        if attack.launch != (0, 0) || knockdown::is_airborne(defender) {
+            knockdown::launch(defender, attack);
+        } else if attack.knockdown != Knockdown::None {
+            knockdown::knock_down(defender, attack.knockdown);
+        } else {
+            defender.status = Status::Hit(attack.hitstun);
+        }
+
+        combo.on_hit(damage);
+    }
+
+    Hit {
+        attacker,
+        blocked,
+        damage: if blocked { 0 } else { damage },
+        kind: HitKind::Normal,
+        at: (defender.center(), defender.y_offset),
+    }
+}
+
+// no stun, one hit of the armor less
+fn absorb(attacker: usize, defender: &mut Player, attack: &Attack) -> Hit {
+    defender.armor -= 1;
+    defender.hp = defender.hp.saturating_sub(attack.damage);
+    defender.hitstop = attack.hitstop;
+
+    Hit {
+        attacker,
+        blocked: false,
+        damage: attack.damage,
+        kind: HitKind::Armor,
+        at: (defender.center(), defender.y_offset),
+    }
+}
+
+fn hit_kind(defender: &Player) -> HitKind {
+    match defender.status {
+        Status::Att(frame) if defender.attack.is_startup(frame) => HitKind::Counter,
+        Status::Att(frame) if defender.attack.is_recovery(frame) => HitKind::Punish,
+        _ => HitKind::Normal,
+    }
+}
+
+// extra hitstun and damage of the attacker
+fn with_bonus(chara: &CharData, kind: HitKind, attack: Attack) -> Attack {
+    let bonus: Bonus = match kind {
+        HitKind::Counter => chara.counter,
+        HitKind::Punish => chara.punish,
+        _ => return attack,
+    };
+
+    Attack {
+        hitstun: attack.hitstun + bonus.hitstun,
+        damage: attack.damage * bonus.damage / 100,
+        ..attack
+    }
+}
+
+// the current frame of the move, in a window of it
+fn in_frames(p: &Player, frames: Option<(u32, u32)>) -> bool {
+    match (p.status, frames) {
+        (Status::Att(frame), Some((from, to))) => from <= frame && frame < to,
+        _ => false,
+    }
+}
+
+fn is_armored(p: &Player) -> bool {
+    p.armor > 0 && in_frames(p, p.attack.armor.map(|a| (a.from, a.to)))
+}
+
+// not on the ground, not in a throw and not invincible
+#[inline(always)]
+pub fn is_hittable(p: &Player) -> bool {
+    !matches!(p.status, Status::Down(_) | Status::Thrown) && p.invincible == 0
+}
+
+////////////////////////////////////////
+// not stunned, not in the air and not already in a throw
+pub fn is_throwable(p: &Player) -> bool {
+    matches!(p.status, Status::Null | Status::Att(_))
+        && p.invincible == 0
+        && !in_frames(p, p.attack.invincible.throw)
+        && p.y_offset == 0
+        && p.vy == 0
+        && !p.machine.is(State::Jump)
+}
+
+pub fn check_throw(attacker: &mut Player, defender: &mut Player) -> Option<Throw> {
+    if attacker.hit_done || !attacker.is_active() || !is_throwable(defender) {
+        return None;
+    }
+
+    let throw = attacker.hitboxes().throw;
+    let push = defender.hitboxes().push;
+
+    if !throw.iter().any(|a| push.is_some_and(|b| a.overlap(&b))) {
+        return None;
+    }
+
+    attacker.hit_done = true;
+    attacker.status = Status::Throw;
+    defender.status = Status::Thrown;
+
+    Some(Throw {
+        attacker: if attacker.is_p1 { 0 } else { 1 },
+        timer: 0,
+        attack: attacker.attack,
+    })
+}
+
+// true: the throw is over
+pub fn update_throw(
+    throw: &mut Throw,
+    attacker: &mut Player,
+    defender: &mut Player,
+    combo: &mut Combo,
+) -> (bool, Option<Hit>) {
+    throw.timer += 1;
+
+    // tech: pushed apart, no damage
+    if throw.timer <= TECH_WINDOW && defender.presses.contains(&(Input::Att, 0)) {
+        let right = defender.center() > attacker.center();
+        let (a, d) = if right {
+            (
+                attacker.x_offset.saturating_sub(TECH_PUSH),
+                defender.x_offset + TECH_PUSH,
+            )
+        } else {
+            (
+                attacker.x_offset + TECH_PUSH,
+                defender.x_offset.saturating_sub(TECH_PUSH),
+            )
+        };
+
+        attacker.x_offset = attacker.clamp_x(a);
+        defender.x_offset = defender.clamp_x(d);
+        attacker.status = Status::Guard(TECH_STUN);
+        defender.status = Status::Guard(TECH_STUN);
+        defender.y_offset = 0;
+
+        return (true, None);
+    }
+
+    // paired: the defender is held in front of the attacker, lifted in an arc
+    let center = if attacker.dire == Dire::Left {
+        attacker.center().saturating_sub(THROW_GAP)
+    } else {
+        attacker.center() + THROW_GAP
+    };
+    let t = throw.timer.min(THROW_TIME);
+
+    defender.x_offset =
+        defender.clamp_x(center.saturating_sub(defender.center() - defender.x_offset));
+    defender.y_offset = THROW_LIFT * 4 * t * (THROW_TIME - t) / (THROW_TIME * THROW_TIME);
+
+    if throw.timer < THROW_TIME {
+        return (false, None);
+    }
+
+    let attack = Attack {
+        knockdown: Knockdown::Hard,
+        launch: (0, 0),
+        ..throw.attack
+    };
+
+    attacker.status = Status::Null;
+    defender.y_offset = 0;
+
+    (
+        true,
+        Some(apply_hit(throw.attacker, defender, &attack, false, combo)),
+    )
+}
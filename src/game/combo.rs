@@ -0,0 +1,143 @@
+//! Combo counter, damage scaling, dropped combos and counter hit notices.
+
+use crate::{
+    game::combat::HitKind,
+    res::font::{Align, Font, TextStyle},
+    window::render::{Layer, RenderQueue},
+};
+
+// percent of the damage, by hit number
+const SCALING: [u32; 8] = [100, 100, 80, 70, 60, 50, 40, 30];
+
+// in ticks
+const DROP_WINDOW: u32 = 20; // a new hit this soon after the end is a drop
+const SHOW_TIME: u32 = 120;
+const NOTICE_TIME: u32 = 60;
+
+const COMBO_COLOR: u32 = 0xfff0d040;
+const DROP_COLOR: u32 = 0xffe04040;
+const NOTICE_COLOR: u32 = 0xff40c0f0;
+
+// of one attacker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Combo {
+    pub hits: u32,
+    pub damage: u32,
+    pub active: bool, // the defender is still stunned
+    pub dropped: bool,
+
+    gap: Option<u32>, // ticks since the last combo ended
+    show: u32,
+
+    notice: Option<HitKind>, // counter, punish OR armor
+    notice_show: u32,
+}
+
+////////////////////////////////////////
+impl Combo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // damage of the next hit
+    #[inline(always)]
+    pub fn scale(&self, damage: u32) -> u32 {
+        let hits = if self.active { self.hits } else { 0 } as usize;
+
+        damage * SCALING[hits.min(SCALING.len() - 1)] / 100
+    }
+
+    // damage is already scaled
+    pub fn on_hit(&mut self, damage: u32) {
+        if !self.active {
+            // the defender was free for a few ticks only
+            self.dropped = self.gap.is_some_and(|gap| gap <= DROP_WINDOW);
+            self.hits = 0;
+            self.damage = 0;
+        }
+
+        self.active = true;
+        self.hits += 1;
+        self.damage += damage;
+        self.gap = None;
+        self.show = SHOW_TIME;
+    }
+
+    pub fn notify(&mut self, kind: HitKind) {
+        if kind != HitKind::Normal {
+            self.notice = Some(kind);
+            self.notice_show = NOTICE_TIME;
+        }
+    }
+
+    // every tick, stunned: the defender can not act
+    pub fn update(&mut self, stunned: bool) {
+        if self.active && !stunned {
+            self.active = false;
+            self.gap = Some(0);
+        } else if let Some(gap) = self.gap.as_mut() {
+            *gap += 1;
+        }
+
+        if !self.active {
+            self.show = self.show.saturating_sub(1);
+        }
+
+        self.notice_show = self.notice_show.saturating_sub(1);
+        if self.notice_show == 0 {
+            self.notice = None;
+        }
+    }
+
+    // flip: on the right side
+    pub fn render<'a>(
+        &self,
+        queue: &mut RenderQueue<'a>,
+        font: &'a Font,
+        (x, y): (i32, i32),
+        flip: bool,
+    ) {
+        let align = if flip { Align::Right } else { Align::Left };
+
+        if let Some(kind) = self.notice {
+            let text = match kind {
+                HitKind::Counter => "COUNTER",
+                HitKind::Punish => "PUNISH COUNTER",
+                _ => "ARMOR",
+            };
+            let style = TextStyle::new(NOTICE_COLOR, 2)
+                .outline(0xff000000)
+                .align(align);
+
+            font.render(queue, Layer::Hud, 10, (x, y - 40), text.to_string(), style);
+        }
+
+        if self.show == 0 {
+            return;
+        }
+
+        if self.hits > 1 {
+            let text = format!("{} HITS\n{} DMG", self.hits, self.damage);
+            let style = TextStyle::new(COMBO_COLOR, 3)
+                .outline(0xff000000)
+                .align(align);
+
+            font.render(queue, Layer::Hud, 10, (x, y), text, style);
+        }
+
+        if self.dropped {
+            let style = TextStyle::new(DROP_COLOR, 2)
+                .outline(0xff000000)
+                .align(align);
+
+            font.render(
+                queue,
+                Layer::Hud,
+                10,
+                (x, y + 40),
+                "DROPPED".to_string(),
+                style,
+            );
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Projectiles and other objects that move on their own.
+
+use crate::{
+    game::combat::Attack,
+    res::{
+        chara::Projectile,
+        sprite::{Dire, Player},
+    },
+    window::{
+        camera::Camera,
+        cube::BoxAABB,
+        render::{Layer, RenderQueue},
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    pub owner: usize, // 0: p1, 1: p2
+    pub name: String,
+
+    pub x: i32, // center, stage coordinates
+    pub y: i32, // center, height above the ground
+    pub vx: i32,
+    pub dire: Dire,
+
+    pub life: u32,
+    pub hp: u32, // durability
+    pub size: (u32, u32),
+    pub attack: Attack,
+
+    // animation, in the stream of the owner
+    packet: usize,
+    frames: usize,
+    ptr_frame: usize,
+    ticks: u32,
+    frame_timer: u32,
+}
+
+////////////////////////////////////////
+impl Entity {
+    pub fn spawn(owner: usize, p: &Player, data: &Projectile) -> Self {
+        let sign = if p.dire == Dire::Left { -1 } else { 1 };
+        let packet = p.find_anim(&data.anim).unwrap();
+
+        Self {
+            owner,
+            name: data.name.clone(),
+            x: p.center() as i32 + sign * data.offset.0 as i32,
+            y: (p.y_offset + data.offset.1) as i32,
+            vx: sign * data.velocity,
+            dire: p.dire,
+            life: data.life,
+            hp: data.durability,
+            size: data.size,
+            attack: data.attack,
+            packet,
+            frames: p.anim_len(packet),
+            ptr_frame: 0,
+            ticks: data.ticks,
+            frame_timer: data.ticks,
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.x += self.vx;
+        self.life = self.life.saturating_sub(1);
+
+        if self.frame_timer > 0 {
+            self.frame_timer -= 1;
+        } else {
+            self.ptr_frame = (self.ptr_frame + 1) % self.frames.max(1);
+            self.frame_timer = self.ticks;
+        }
+    }
+
+    // the animation of the owner was reloaded
+    pub fn reload(&mut self, owner: &Player) {
+        self.frames = owner.anim_len(self.packet);
+        self.ptr_frame = self.ptr_frame.min(self.frames.saturating_sub(1));
+    }
+
+    #[inline(always)]
+    pub fn is_alive(&self) -> bool {
+        self.life > 0 && self.hp > 0
+    }
+
+    // stage coordinates, y is the height above the ground
+    pub fn hitbox(&self) -> BoxAABB {
+        let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+
+        BoxAABB::new(
+            (self.x - w / 2).max(0) as u32,
+            (self.x + w / 2).max(0) as u32,
+            (self.y - h / 2).max(0) as u32,
+            (self.y + h / 2).max(0) as u32,
+        )
+    }
+
+    // the sprite is centered on (x, y)
+    pub fn render<'a>(
+        &self,
+        queue: &mut RenderQueue<'a>,
+        camera: &Camera,
+        ground: u32,
+        owner: &'a Player,
+    ) {
+        let (draw, (ox, oy), (width, height)) =
+            owner.anim_frame(self.packet, self.ptr_frame, self.dire);
+
+        let x = self.x - width as i32 / 2 - camera.x;
+        let y = ground as i32 - self.y - height as i32 / 2 - camera.y;
+
+        queue.push(Layer::Player, 2, x + ox, y + oy, draw);
+    }
+}
+
+////////////////////////////////////////
+// projectiles of different owners destroy each other
+pub fn clash(entities: &mut [Entity]) {
+    for i in 0..entities.len() {
+        for j in i + 1..entities.len() {
+            let (a, b) = (&entities[i], &entities[j]);
+
+            if a.owner == b.owner || !a.is_alive() || !b.is_alive() {
+                continue;
+            }
+
+            if a.hitbox().overlap(&b.hitbox()) {
+                entities[i].hp -= 1;
+                entities[j].hp -= 1;
+            }
+        }
+    }
+}
+
+// on screen, of one owner
+pub fn count(entities: &[Entity], owner: usize, name: &str) -> u32 {
+    entities
+        .iter()
+        .filter(|e| e.owner == owner && e.name == name)
+        .count() as u32
+}
@@ -0,0 +1,179 @@
+//! Launches, juggles, bounces, knockdowns and wakeup.
+
+use crate::{
+    game::{
+        combat::Attack,
+        state::{KNOCKDOWN_TIME, WAKEUP_TIME},
+    },
+    res::{
+        chara::Input,
+        sprite::{Player, Status},
+    },
+};
+
+// in ticks
+const HARD_KNOCKDOWN_TIME: u32 = 90;
+const DELAY_TIME: u32 = 40; // added by a delayed wakeup
+const WAKEUP_INVINCIBLE: u32 = 6; // after getting up
+
+// px per tick
+const JUGGLE_LAUNCH: (i32, i32) = (2, 10); // hit in the air without a launch
+const BOUNCE_SPEED: i32 = 14;
+
+// points of all the hits in the air, from the launch to the landing
+pub const JUGGLE_LIMIT: u32 = 6;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Knockdown {
+    #[default]
+    None,
+    Soft, // can quick-rise
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bounce {
+    #[default]
+    None,
+    Ground,
+    Wall,
+}
+
+// chosen while lying on the ground
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rise {
+    #[default]
+    Normal,
+    Quick,
+    Delayed,
+}
+
+////////////////////////////////////////
+impl Knockdown {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "" | "soft" => Some(Knockdown::Soft),
+            "hard" => Some(Knockdown::Hard),
+            _ => None,
+        }
+    }
+
+    // lying on the ground, before the wakeup
+    #[inline(always)]
+    fn time(&self) -> u32 {
+        match self {
+            Knockdown::Hard => HARD_KNOCKDOWN_TIME,
+            _ => KNOCKDOWN_TIME,
+        }
+    }
+}
+
+impl Bounce {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ground" => Some(Bounce::Ground),
+            "wall" => Some(Bounce::Wall),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////
+// the defender goes up, away from the attacker
+pub fn launch(p: &mut Player, attack: &Attack) {
+    let (back, up) = if attack.launch == (0, 0) {
+        JUGGLE_LAUNCH
+    } else {
+        attack.launch
+    };
+
+    // a new juggle
+    if p.status != Status::Air {
+        p.juggle = 0;
+        p.down = Knockdown::Soft;
+    }
+
+    p.vx = -back;
+    p.vy = up;
+    p.juggle += attack.juggle;
+    p.down = p.down.max(attack.knockdown);
+    if attack.bounce != Bounce::None {
+        p.bounce = attack.bounce;
+    }
+    p.status = Status::Air;
+}
+
+pub fn knock_down(p: &mut Player, kind: Knockdown) {
+    p.vx = 0;
+    p.juggle = 0;
+    p.bounce = Bounce::None;
+    p.down = kind;
+    p.rise = Rise::Normal;
+    p.status = Status::Down(kind.time() + WAKEUP_TIME);
+}
+
+// no more hits in the air after the limit
+#[inline(always)]
+pub fn can_juggle(p: &Player, attack: &Attack) -> bool {
+    p.status != Status::Air || p.juggle + attack.juggle <= JUGGLE_LIMIT
+}
+
+#[inline(always)]
+pub fn is_airborne(p: &Player) -> bool {
+    p.y_offset > 0 || p.vy != 0
+}
+
+// every tick, before update_status()
+pub fn update(p: &mut Player) {
+    p.invincible = p.invincible.saturating_sub(1);
+
+    match p.status {
+        Status::Air => fall(p),
+        Status::Down(left) => lie(p, left),
+        _ => {}
+    }
+}
+
+// gravity is in state::apply_motion()
+fn fall(p: &mut Player) {
+    let x = p.x_offset;
+    p.move_forward(p.vx);
+
+    if p.bounce == Bounce::Wall && p.vx != 0 && p.x_offset == x {
+        p.bounce = Bounce::None;
+        p.vx = -p.vx / 2;
+        p.vy = p.vy.max(BOUNCE_SPEED / 2);
+        return;
+    }
+
+    if is_airborne(p) {
+        return;
+    }
+
+    if p.bounce == Bounce::Ground {
+        p.bounce = Bounce::None;
+        p.vx /= 2;
+        p.vy = BOUNCE_SPEED;
+    } else {
+        knock_down(p, p.down);
+    }
+}
+
+fn lie(p: &mut Player, left: u32) {
+    if left == 1 {
+        p.invincible = WAKEUP_INVINCIBLE;
+        return;
+    }
+
+    if left <= WAKEUP_TIME || p.rise != Rise::Normal {
+        return;
+    }
+
+    if p.down == Knockdown::Soft && p.presses.contains(&(Input::Att, 0)) {
+        p.rise = Rise::Quick;
+        p.status = Status::Down(WAKEUP_TIME + 1);
+    } else if p.presses.contains(&(Input::Back, 0)) {
+        p.rise = Rise::Delayed;
+        p.status = Status::Down(left + DELAY_TIME);
+    }
+}
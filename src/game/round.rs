@@ -0,0 +1,242 @@
+//! Rounds and the match: intro, KO, time over, best-of-N and the result.
+
+use crate::{
+    game::sim::Game,
+    res::{
+        font::{Align, Font, TextStyle},
+        sprite::Dire,
+    },
+    window::render::{Layer, RenderQueue},
+    FPS,
+};
+
+// ticks per second
+const SECOND: u32 = 1000 / FPS;
+
+const INTRO_TIME: u32 = SECOND * 2;
+const READY_TIME: u32 = SECOND;
+const FIGHT_SHOW: u32 = SECOND; // "FIGHT!" stays while fighting
+const KO_TIME: u32 = SECOND * 3;
+
+pub const ROUND_TIME: u32 = 99; // seconds
+
+// distance of the players from the center of the stage
+const START_GAP: u32 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Intro(u32), // ticks left
+    Ready(u32),
+    Fight,
+    Ko(u32),
+    TimeOver(u32),
+    End,
+}
+
+// of a round or of the match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winner {
+    P1,
+    P2,
+    Draw,
+}
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub phase: Phase,
+    pub best_of: u32,
+    pub round: u32, // from 1
+    pub wins: [u32; 2],
+    pub round_time: Option<u32>, // seconds, None: no time limit
+
+    timer: u32, // ticks left in the round
+    fight_timer: u32,
+    pub last: Option<Winner>, // of the last round
+    pub result: Option<Winner>,
+}
+
+////////////////////////////////////////
+impl Winner {
+    // by remaining hp
+    fn by_hp(hp1: u32, hp2: u32) -> Self {
+        match hp1.cmp(&hp2) {
+            std::cmp::Ordering::Greater => Winner::P1,
+            std::cmp::Ordering::Less => Winner::P2,
+            std::cmp::Ordering::Equal => Winner::Draw,
+        }
+    }
+}
+
+impl Match {
+    pub fn new(best_of: u32, round_time: Option<u32>) -> Self {
+        Self {
+            phase: Phase::Intro(INTRO_TIME),
+            best_of: best_of.max(1),
+            round: 1,
+            wins: [0; 2],
+            round_time,
+            timer: round_time.unwrap_or(0) * SECOND,
+            fight_timer: 0,
+            last: None,
+            result: None,
+        }
+    }
+
+    // rounds to win the match
+    #[inline(always)]
+    pub fn need(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    // inputs are ignored outside of the fight
+    #[inline(always)]
+    pub fn can_fight(&self) -> bool {
+        self.phase == Phase::Fight
+    }
+
+    #[inline(always)]
+    pub fn is_over(&self) -> bool {
+        self.phase == Phase::End
+    }
+
+    // for the hud, rounded up
+    pub fn seconds(&self) -> Option<u32> {
+        self.round_time.map(|_| self.timer.div_ceil(SECOND))
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.wins.iter().all(|&w| w + 1 == self.need())
+    }
+
+    // players go back to their positions with full hp
+    pub fn start(&mut self, game: &mut Game, stage_width: u32) {
+        let center = stage_width / 2;
+
+        game.p1.reset(center.saturating_sub(START_GAP), Dire::Right);
+        game.p2.reset(center + START_GAP, Dire::Left);
+        game.clear();
+
+        self.phase = Phase::Intro(INTRO_TIME);
+        self.timer = self.round_time.unwrap_or(0) * SECOND;
+        self.fight_timer = 0;
+    }
+
+    // after Game::update()
+    pub fn update(&mut self, game: &mut Game, stage_width: u32) {
+        self.phase = match self.phase {
+            Phase::Intro(0) => Phase::Ready(READY_TIME),
+            Phase::Intro(t) => Phase::Intro(t - 1),
+            Phase::Ready(0) => Phase::Fight,
+            Phase::Ready(t) => Phase::Ready(t - 1),
+
+            Phase::Fight => {
+                self.fight_timer += 1;
+
+                // the clock stops for the super flash
+                if self.round_time.is_some() && game.freeze == 0 {
+                    self.timer = self.timer.saturating_sub(1);
+                }
+
+                let (hp1, hp2) = (game.p1.hp, game.p2.hp);
+
+                if hp1 == 0 || hp2 == 0 {
+                    self.end_round(Winner::by_hp(hp1, hp2));
+                    Phase::Ko(KO_TIME)
+                } else if self.round_time.is_some() && self.timer == 0 {
+                    self.end_round(Winner::by_hp(hp1, hp2));
+                    Phase::TimeOver(KO_TIME)
+                } else {
+                    Phase::Fight
+                }
+            }
+
+            Phase::Ko(0) | Phase::TimeOver(0) => {
+                if let Some(result) = self.check_result() {
+                    self.result = Some(result);
+                    Phase::End
+                } else {
+                    self.round += 1;
+                    self.start(game, stage_width);
+                    self.phase
+                }
+            }
+
+            Phase::Ko(t) => Phase::Ko(t - 1),
+            Phase::TimeOver(t) => Phase::TimeOver(t - 1),
+            Phase::End => Phase::End,
+        };
+    }
+
+    // a draw is a win for both
+    fn end_round(&mut self, winner: Winner) {
+        match winner {
+            Winner::P1 => self.wins[0] += 1,
+            Winner::P2 => self.wins[1] += 1,
+            Winner::Draw => {
+                self.wins[0] += 1;
+                self.wins[1] += 1;
+            }
+        }
+
+        self.last = Some(winner);
+    }
+
+    fn check_result(&self) -> Option<Winner> {
+        let need = self.need();
+
+        match (self.wins[0] >= need, self.wins[1] >= need) {
+            (true, true) => Some(Winner::Draw),
+            (true, false) => Some(Winner::P1),
+            (false, true) => Some(Winner::P2),
+            (false, false) => None,
+        }
+    }
+
+    pub fn banner(&self, names: &[String; 2]) -> Option<String> {
+        let text = match self.phase {
+            Phase::Intro(_) if self.is_final() => "FINAL ROUND".to_string(),
+            Phase::Intro(_) => format!("ROUND {}", self.round),
+            Phase::Ready(_) => "READY".to_string(),
+            Phase::Fight if self.fight_timer < FIGHT_SHOW => "FIGHT!".to_string(),
+            Phase::Fight => return None,
+
+            Phase::Ko(_) if self.last == Some(Winner::Draw) => "DOUBLE KO".to_string(),
+            Phase::Ko(_) => "KO".to_string(),
+            Phase::TimeOver(_) if self.last == Some(Winner::Draw) => "TIME\nDRAW".to_string(),
+            Phase::TimeOver(_) => "TIME".to_string(),
+
+            Phase::End => match self.result {
+                Some(Winner::P1) => format!("{} WINS\nPRESS ENTER", names[0]),
+                Some(Winner::P2) => format!("{} WINS\nPRESS ENTER", names[1]),
+                _ => "DRAW\nPRESS ENTER".to_string(),
+            },
+        };
+
+        Some(text)
+    }
+
+    pub fn render<'a>(
+        &self,
+        queue: &mut RenderQueue<'a>,
+        font: &'a Font,
+        names: &[String; 2],
+        (width, height): (u32, u32),
+    ) {
+        let Some(text) = self.banner(names) else {
+            return;
+        };
+
+        let style = TextStyle::new(0xffffffff, 8)
+            .outline(0xff000000)
+            .align(Align::Center);
+
+        font.render(
+            queue,
+            Layer::Hud,
+            20,
+            (width as i32 / 2, height as i32 / 3),
+            text,
+            style,
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! One tick of the fight, independent of the window.
+
+use crate::{
+    game::{
+        combat::{self, Hit, HitKind, Throw},
+        combo::Combo,
+        entity::{self, Entity},
+        knockdown,
+    },
+    res::{
+        event::Event,
+        sprite::{KeyMap, Player},
+    },
+    FPS,
+};
+use std::path::Path;
+
+// ticks, the world stops when a super starts
+const FLASH_TIME: u32 = 400 / FPS; // 0.4 s
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub p1: Player,
+    pub p2: Player,
+
+    pub tick: u64,
+    pub hits: Vec<Hit>,              // in this tick
+    pub sounds: Vec<String>,         // in this tick, of the moves and the frames
+    pub events: Vec<(usize, Event)>, // in this tick, by player
+    pub combos: [Combo; 2],          // by attacker
+    pub entities: Vec<Entity>,
+    pub throw: Option<Throw>,
+
+    pub freeze: u32,          // ticks left of the super flash
+    pub flash: Option<usize>, // who started the super
+}
+
+////////////////////////////////////////
+impl Game {
+    pub fn new(p1: Player, p2: Player) -> Self {
+        Self {
+            p1,
+            p2,
+            tick: 0,
+            hits: vec![],
+            sounds: vec![],
+            events: vec![],
+            combos: [Combo::new(); 2],
+            entities: vec![],
+            throw: None,
+            freeze: 0,
+            flash: None,
+        }
+    }
+
+    // hot reload, false: nothing uses the file
+    pub fn reload(&mut self, path: &Path) -> bool {
+        let done = [self.p1.reload(path), self.p2.reload(path)];
+
+        for e in self.entities.iter_mut() {
+            e.reload(if e.owner == 0 { &self.p1 } else { &self.p2 });
+        }
+
+        done.contains(&true)
+    }
+
+    // between rounds
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.throw = None;
+        self.freeze = 0;
+        self.flash = None;
+    }
+
+    pub fn update(&mut self, k1: &[KeyMap], k2: &[KeyMap]) {
+        self.hits.clear();
+        self.sounds.clear();
+        self.events.clear();
+
+        if self.freeze > 0 {
+            self.freeze -= 1;
+            if self.freeze == 0 {
+                self.flash = None;
+            }
+
+            self.tick += 1;
+            return;
+        }
+
+        let moved = [step(&mut self.p1, k1), step(&mut self.p2, k2)];
+
+        for (idx, p) in [&self.p1, &self.p2].into_iter().enumerate() {
+            if !moved[idx] {
+                continue;
+            }
+
+            self.sounds.extend(p.sounding().map(String::from));
+            for event in p.events.iter() {
+                if let Event::Sound(name) = event {
+                    self.sounds.push(name.clone());
+                }
+                self.events.push((idx, event.clone()));
+            }
+        }
+
+        for (idx, p) in [&self.p1, &self.p2].into_iter().enumerate() {
+            if p.is_super_start() {
+                self.freeze = FLASH_TIME;
+                self.flash = Some(idx);
+            }
+        }
+
+        let (c1, c2) = (self.p1.center(), self.p2.center());
+        self.p1.face_toward(c2);
+        self.p2.face_toward(c1);
+
+        self.update_entities(moved);
+        self.update_throw();
+
+        if let Some(hit) = combat::check_hit(&mut self.p1, &mut self.p2, &mut self.combos[0]) {
+            self.hits.push(hit);
+        }
+
+        if let Some(hit) = combat::check_hit(&mut self.p2, &mut self.p1, &mut self.combos[1]) {
+            self.hits.push(hit);
+        }
+
+        self.gain_meter();
+
+        for hit in self.hits.iter() {
+            // armor is a notice for the defender
+            let side = if hit.kind == HitKind::Armor {
+                1 - hit.attacker
+            } else {
+                hit.attacker
+            };
+            self.combos[side].notify(hit.kind);
+        }
+
+        self.combos[0].update(self.p2.is_stunned());
+        self.combos[1].update(self.p1.is_stunned());
+
+        self.tick += 1;
+    }
+
+    // for the hits of this tick, half on block
+    fn gain_meter(&mut self) {
+        for hit in self.hits.iter() {
+            let (attacker, defender) = if hit.attacker == 0 {
+                (&mut self.p1, &mut self.p2)
+            } else {
+                (&mut self.p2, &mut self.p1)
+            };
+            let div = if hit.blocked { 2 } else { 1 };
+
+            attacker.gain_ep(attacker.chara.meter.hit / div);
+            defender.gain_ep(defender.chara.meter.hurt / div);
+        }
+    }
+
+    // p1 wins a throw trade
+    fn update_throw(&mut self) {
+        if self.throw.is_none() {
+            self.throw = combat::check_throw(&mut self.p1, &mut self.p2)
+                .or_else(|| combat::check_throw(&mut self.p2, &mut self.p1));
+        }
+
+        let Some(throw) = self.throw.as_mut() else {
+            return;
+        };
+
+        let (attacker, defender) = if throw.attacker == 0 {
+            (&mut self.p1, &mut self.p2)
+        } else {
+            (&mut self.p2, &mut self.p1)
+        };
+
+        let (done, hit) =
+            combat::update_throw(throw, attacker, defender, &mut self.combos[throw.attacker]);
+
+        if let Some(hit) = hit {
+            self.hits.push(hit);
+        }
+
+        if done {
+            self.throw = None;
+        }
+    }
+
+    // spawn, move, clash and hit
+    // moved: not in hitstop, the frame of the move went on
+    fn update_entities(&mut self, moved: [bool; 2]) {
+        for (owner, p) in [&self.p1, &self.p2].into_iter().enumerate() {
+            if !moved[owner] {
+                continue;
+            }
+
+            let events = p.events.iter().filter_map(|e| match e {
+                Event::Spawn(name) => p.chara.projectile(name),
+                _ => None,
+            });
+
+            for data in p.spawning().into_iter().chain(events) {
+                if entity::count(&self.entities, owner, &data.name) < data.limit {
+                    self.entities.push(Entity::spawn(owner, p, data));
+                }
+            }
+        }
+
+        for e in self.entities.iter_mut() {
+            e.update();
+        }
+
+        entity::clash(&mut self.entities);
+
+        for e in self.entities.iter_mut() {
+            let (defender, combo) = if e.owner == 0 {
+                (&mut self.p2, &mut self.combos[0])
+            } else {
+                (&mut self.p1, &mut self.combos[1])
+            };
+
+            if let Some(hit) = combat::check_entity_hit(e, defender, combo) {
+                self.hits.push(hit);
+            }
+        }
+
+        self.entities.retain(Entity::is_alive);
+    }
+
+    #[inline(always)]
+    pub fn players(&self) -> [&Player; 2] {
+        [&self.p1, &self.p2]
+    }
+}
+
+////////////////////////////////////////
+// a player in hitstop only buffers inputs, the rest of the world goes on
+// false: frozen in this tick
+fn step(p: &mut Player, keys: &[KeyMap]) -> bool {
+    if p.hitstop > 0 {
+        p.hold(keys);
+        return false;
+    }
+
+    // status first, so a new attack starts at frame 0
+    knockdown::update(p);
+    p.update_status();
+    p.check_input(keys);
+    p.next_frame();
+
+    true
+}
@@ -0,0 +1,275 @@
+//! Character state machine: named states, entry/exit hooks and transitions.
+
+use crate::res::{
+    chara::Input,
+    sprite::{Player, Status},
+};
+
+// in ticks
+const DASH_WINDOW: u32 = 16; // between the two taps
+const DASH_TIME: u32 = 20;
+pub const KNOCKDOWN_TIME: u32 = 60;
+pub const WAKEUP_TIME: u32 = 30;
+
+// px per tick
+const JUMP_SPEED: i32 = 24;
+const GRAVITY: i32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Idle,
+    WalkForward,
+    WalkBack,
+    Dash,
+    Run,
+    Jump,
+    Crouch,
+    Attack,
+    Hitstun,
+    Blockstun,
+    AirHit,
+    Knockdown,
+    Wakeup,
+    Thrown,
+}
+
+// from: empty for any state
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub from: &'static [State],
+    pub to: State,
+    pub cond: fn(&Player) -> bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    pub state: State,
+    pub prev: State,
+    pub timer: u32, // ticks in this state
+
+    pub transitions: Vec<Transition>, // the first one that matches
+}
+
+////////////////////////////////////////
+impl State {
+    // name in CharData
+    pub fn name(&self) -> &'static str {
+        match self {
+            State::Idle => "idle",
+            State::WalkForward => "walk_fwd",
+            State::WalkBack => "walk_back",
+            State::Dash => "dash",
+            State::Run => "run",
+            State::Jump => "jump",
+            State::Crouch => "crouch",
+            State::Attack => "attack",
+            State::Hitstun => "hitstun",
+            State::Blockstun => "blockstun",
+            State::AirHit => "air_hit",
+            State::Knockdown => "knockdown",
+            State::Wakeup => "wakeup",
+            State::Thrown => "thrown",
+        }
+    }
+
+    // Attack uses the animation of the move
+    pub fn all() -> [State; 14] {
+        [
+            State::Idle,
+            State::WalkForward,
+            State::WalkBack,
+            State::Dash,
+            State::Run,
+            State::Jump,
+            State::Crouch,
+            State::Attack,
+            State::Hitstun,
+            State::Blockstun,
+            State::AirHit,
+            State::Knockdown,
+            State::Wakeup,
+            State::Thrown,
+        ]
+    }
+
+    // on the ground and free
+    #[inline(always)]
+    pub fn is_neutral(&self) -> bool {
+        matches!(
+            self,
+            State::Idle | State::WalkForward | State::WalkBack | State::Crouch
+        )
+    }
+
+    fn enter(self, p: &mut Player) {
+        match self {
+            State::Jump => {
+                p.vy = JUMP_SPEED;
+                p.jump_dir = if p.holds(Input::Forward) {
+                    1
+                } else if p.holds(Input::Back) {
+                    -1
+                } else {
+                    0
+                };
+            }
+
+            // a new move, its own animation
+            State::Attack => p.ptr_frame = 0,
+
+            _ => {}
+        }
+    }
+
+    fn exit(self, p: &mut Player) {
+        if self == State::Jump {
+            p.jump_dir = 0;
+        }
+    }
+}
+
+impl Transition {
+    pub fn new(from: &'static [State], to: State, cond: fn(&Player) -> bool) -> Self {
+        Self { from, to, cond }
+    }
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        use State::*;
+
+        let neutral: &'static [State] = &[Idle, WalkForward, WalkBack, Crouch];
+
+        Self {
+            state: Idle,
+            prev: Idle,
+            timer: 0,
+            transitions: vec![
+                // status first
+                Transition::new(&[], Hitstun, |p| matches!(p.status, Status::Hit(_))),
+                Transition::new(&[], Blockstun, |p| matches!(p.status, Status::Guard(_))),
+                Transition::new(&[], Attack, |p| p.is_attacking()),
+                Transition::new(&[], Thrown, |p| p.status == Status::Thrown),
+                Transition::new(&[], AirHit, |p| p.status == Status::Air),
+                Transition::new(
+                    &[],
+                    Knockdown,
+                    |p| matches!(p.status, Status::Down(left) if left > WAKEUP_TIME),
+                ),
+                Transition::new(
+                    &[],
+                    Wakeup,
+                    |p| matches!(p.status, Status::Down(left) if left <= WAKEUP_TIME),
+                ),
+                Transition::new(
+                    &[
+                        Attack, Hitstun, Blockstun, AirHit, Knockdown, Wakeup, Thrown,
+                    ],
+                    Idle,
+                    |p| p.status == Status::Null,
+                ),
+                // in the air
+                Transition::new(&[Jump], Idle, |p| {
+                    p.machine.timer > 0 && p.y_offset == 0 && p.vy == 0
+                }),
+                // on the ground
+                Transition::new(
+                    &[Idle, WalkForward, WalkBack, Crouch, Dash, Run],
+                    Jump,
+                    |p| p.holds(Input::Up) && p.y_offset == 0 && p.vy == 0,
+                ),
+                Transition::new(&[Idle, WalkForward, WalkBack], Dash, |p| {
+                    is_double_tap(&p.presses)
+                }),
+                Transition::new(&[Dash], Run, |p| {
+                    p.machine.timer >= DASH_TIME && p.holds(Input::Forward)
+                }),
+                Transition::new(&[Dash], Idle, |p| p.machine.timer >= DASH_TIME),
+                Transition::new(&[Run], Idle, |p| !p.holds(Input::Forward)),
+                Transition::new(neutral, Crouch, |p| p.holds(Input::Down)),
+                Transition::new(&[Crouch], Idle, |p| !p.holds(Input::Down)),
+                Transition::new(&[Idle, WalkBack], WalkForward, |p| p.holds(Input::Forward)),
+                Transition::new(&[Idle, WalkForward], WalkBack, |p| {
+                    p.holds(Input::Back) && !p.holds(Input::Forward)
+                }),
+                Transition::new(&[WalkForward, WalkBack], Idle, |p| {
+                    !p.holds(Input::Forward) && !p.holds(Input::Back)
+                }),
+            ],
+        }
+    }
+
+    #[inline(always)]
+    pub fn is(&self, state: State) -> bool {
+        self.state == state
+    }
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+////////////////////////////////////////
+// one transition at most per tick
+pub fn update(p: &mut Player) {
+    let state = p.machine.state;
+    let next = p
+        .machine
+        .transitions
+        .iter()
+        .find(|t| t.to != state && (t.from.is_empty() || t.from.contains(&state)) && (t.cond)(p))
+        .map(|t| t.to);
+
+    p.machine.timer += 1;
+
+    if let Some(next) = next {
+        switch(p, next);
+    }
+}
+
+pub fn switch(p: &mut Player, next: State) {
+    p.machine.state.exit(p);
+
+    p.machine.prev = p.machine.state;
+    p.machine.state = next;
+    p.machine.timer = 0;
+
+    next.enter(p);
+}
+
+// the velocity of the state, and gravity
+pub fn apply_motion(p: &mut Player) {
+    let velocity = p.current().velocity;
+
+    match p.machine.state {
+        State::Jump => p.move_forward(velocity * p.jump_dir),
+        // AirHit: knockdown::update()
+        State::Hitstun | State::Blockstun | State::AirHit | State::Knockdown | State::Wakeup => {}
+
+        // held by the thrower
+        State::Thrown => return,
+
+        // vx: frame events
+        _ => p.move_forward(velocity + p.vx),
+    }
+
+    if p.vy != 0 || p.y_offset > 0 {
+        p.y_offset = (p.y_offset as i32 + p.vy).max(0) as u32;
+        p.vy -= GRAVITY;
+
+        if p.y_offset == 0 {
+            p.vy = 0;
+        }
+    }
+}
+
+// the last press is a second tap of forward
+pub fn is_double_tap(presses: &[(Input, u32)]) -> bool {
+    match presses {
+        [.., (Input::Forward, age0), (Input::Forward, 0)] => *age0 <= DASH_WINDOW,
+        _ => false,
+    }
+}
@@ -0,0 +1,315 @@
+//! Training mode: refills, position reset, dummy behaviour and frame data.
+
+use crate::{
+    game::sim::Game,
+    res::{
+        font::{Font, TextStyle},
+        sprite::{Dire, KeyMap, Status, HP_MAX},
+    },
+    window::render::{Layer, RenderQueue},
+};
+use minifb::{Key, KeyRepeat, Window};
+
+// GuardAfterHit: keep guarding for a while after the last hit
+const GUARD_TIME: u32 = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dummy {
+    #[default]
+    Stand,
+    Crouch,
+    Jump,
+    GuardAll,
+    GuardAfterHit,
+    Record,
+    Playback,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    #[default]
+    Center,
+    LeftCorner,
+    RightCorner,
+}
+
+// of the last move of p1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameData {
+    pub startup: u32,
+    pub active: u32,
+    pub recovery: u32,
+    pub on_hit: Option<i32>,
+    pub on_block: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Meter {
+    data: FrameData,
+    blocked: Option<bool>, // None: whiffed
+    attacker_free: Option<u64>,
+    defender_free: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Training {
+    pub dummy: Dummy,
+    pub preset: Preset,
+    pub refill: bool,
+
+    record: Vec<Vec<KeyMap>>,
+    recording: bool,
+    ptr_record: usize,
+    guard_timer: u32,
+
+    meter: Option<Meter>,
+    status: Option<Status>, // of p1, at the last tick
+    pub last: Option<FrameData>,
+}
+
+////////////////////////////////////////
+impl Dummy {
+    pub fn next(self) -> Self {
+        match self {
+            Dummy::Stand => Dummy::Crouch,
+            Dummy::Crouch => Dummy::Jump,
+            Dummy::Jump => Dummy::GuardAll,
+            Dummy::GuardAll => Dummy::GuardAfterHit,
+            Dummy::GuardAfterHit => Dummy::Record,
+            Dummy::Record => Dummy::Playback,
+            Dummy::Playback => Dummy::Stand,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dummy::Stand => "STAND",
+            Dummy::Crouch => "CROUCH",
+            Dummy::Jump => "JUMP",
+            Dummy::GuardAll => "GUARD ALL",
+            Dummy::GuardAfterHit => "GUARD AFTER HIT",
+            Dummy::Record => "RECORD",
+            Dummy::Playback => "PLAYBACK",
+        }
+    }
+}
+
+impl Preset {
+    pub fn next(self) -> Self {
+        match self {
+            Preset::Center => Preset::LeftCorner,
+            Preset::LeftCorner => Preset::RightCorner,
+            Preset::RightCorner => Preset::Center,
+        }
+    }
+
+    // centers of p1 and p2
+    pub fn positions(&self, stage_width: u32) -> (u32, u32) {
+        let w = stage_width;
+
+        match self {
+            Preset::Center => ((w / 2).saturating_sub(150), w / 2 + 150),
+            Preset::LeftCorner => (w.min(450), w.min(150)),
+            Preset::RightCorner => (w.saturating_sub(450), w.saturating_sub(150)),
+        }
+    }
+}
+
+impl Training {
+    pub fn new() -> Self {
+        Self {
+            refill: true,
+            ..Self::default()
+        }
+    }
+
+    // F3: dummy, F4: reset position, F5: next position
+    pub fn check_keys(&mut self, window: &Window, game: &mut Game, stage_width: u32) {
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            self.dummy = self.dummy.next();
+        }
+
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            self.preset = self.preset.next();
+            self.reset(game, stage_width);
+        } else if window.is_key_pressed(Key::F4, KeyRepeat::No) {
+            self.reset(game, stage_width);
+        }
+    }
+
+    // p2 is the dummy, `keys` are the keys of p2 on the keyboard
+    pub fn dummy_input(&mut self, game: &Game, keys: &[KeyMap]) -> Vec<KeyMap> {
+        let back = if game.p1.center() > game.p2.center() {
+            KeyMap::Left
+        } else {
+            KeyMap::Right
+        };
+
+        match self.dummy {
+            Dummy::Stand => vec![],
+            Dummy::Crouch => vec![KeyMap::Down],
+            Dummy::Jump => vec![KeyMap::Up],
+            Dummy::GuardAll => vec![back],
+
+            Dummy::GuardAfterHit => {
+                if self.guard_timer > 0 {
+                    vec![back]
+                } else {
+                    vec![]
+                }
+            }
+
+            // p2 controls the dummy while recording
+            Dummy::Record => {
+                if !self.recording {
+                    self.record.clear();
+                    self.recording = true;
+                    self.ptr_record = 0;
+                }
+
+                self.record.push(keys.to_vec());
+                keys.to_vec()
+            }
+
+            // loop
+            Dummy::Playback => {
+                self.recording = false;
+
+                if self.record.is_empty() {
+                    return vec![];
+                }
+
+                let res = self.record[self.ptr_record % self.record.len()].clone();
+                self.ptr_record += 1;
+
+                res
+            }
+        }
+    }
+
+    // after Game::update()
+    pub fn update(&mut self, game: &mut Game) {
+        if self.dummy == Dummy::GuardAfterHit {
+            if game.hits.iter().any(|hit| hit.attacker == 0) {
+                self.guard_timer = GUARD_TIME;
+            } else {
+                self.guard_timer = self.guard_timer.saturating_sub(1);
+            }
+        }
+
+        self.observe(game);
+
+        if self.refill {
+            for p in [&mut game.p1, &mut game.p2] {
+                if p.is_actionable() {
+                    p.hp = HP_MAX;
+                    p.ep = p.chara.meter.max;
+                }
+            }
+        }
+    }
+
+    pub fn reset(&mut self, game: &mut Game, stage_width: u32) {
+        let (x1, x2) = self.preset.positions(stage_width);
+        // facing each other: p2 is the one cornered in LeftCorner
+        let (d1, d2) = if x1 <= x2 {
+            (Dire::Right, Dire::Left)
+        } else {
+            (Dire::Left, Dire::Right)
+        };
+
+        for (p, x, dire) in [(&mut game.p1, x1, d1), (&mut game.p2, x2, d2)] {
+            p.reset(x, dire);
+            p.ep = p.chara.meter.max;
+        }
+
+        game.clear();
+        self.meter = None;
+        self.guard_timer = 0;
+    }
+
+    // frame data of p1, measured tick by tick
+    fn observe(&mut self, game: &Game) {
+        let (a, d) = (&game.p1, &game.p2);
+
+        // the frame went on: not in hitstop OR a super flash
+        let moved = self.status != Some(a.status);
+        self.status = Some(a.status);
+
+        if a.status == Status::Att(0) && moved {
+            self.meter = Some(Meter::default());
+        }
+
+        let Some(meter) = self.meter.as_mut() else {
+            return;
+        };
+
+        if let (Status::Att(frame), true) = (a.status, moved) {
+            if a.is_active() {
+                if meter.data.active == 0 {
+                    meter.data.startup = frame + 1;
+                }
+
+                meter.data.active += 1;
+            } else if meter.data.active > 0 {
+                meter.data.recovery += 1;
+            }
+        }
+
+        if let Some(hit) = game.hits.iter().find(|hit| hit.attacker == 0) {
+            meter.blocked = Some(hit.blocked);
+        }
+
+        if meter.blocked.is_some() {
+            if meter.attacker_free.is_none() && a.is_actionable() {
+                meter.attacker_free = Some(game.tick);
+            }
+
+            if meter.defender_free.is_none() && d.is_actionable() {
+                meter.defender_free = Some(game.tick);
+            }
+        }
+
+        let done = match (meter.blocked, meter.attacker_free, meter.defender_free) {
+            (None, _, _) => a.is_actionable(),
+            (Some(blocked), Some(a), Some(d)) => {
+                let adv = Some(d as i32 - a as i32);
+
+                if blocked {
+                    meter.data.on_block = adv;
+                } else {
+                    meter.data.on_hit = adv;
+                }
+
+                true
+            }
+
+            _ => false,
+        };
+
+        if done {
+            self.last = Some(meter.data);
+            self.meter = None;
+        }
+    }
+
+    pub fn render<'a>(&self, queue: &mut RenderQueue<'a>, font: &'a Font) {
+        let style = TextStyle::new(0xffffffff, 2).outline(0xff000000);
+        let adv = |v: Option<i32>| v.map_or("-".to_string(), |v| format!("{:+}", v));
+
+        let mut text = format!("DUMMY: {}\n", self.dummy.name());
+
+        if let Some(data) = self.last {
+            text += &format!(
+                "STARTUP {} ACTIVE {} RECOVERY {}\nON HIT {} ON BLOCK {}",
+                data.startup,
+                data.active,
+                data.recovery,
+                adv(data.on_hit),
+                adv(data.on_block),
+            );
+        }
+
+        font.render(queue, Layer::Hud, 10, (20, 100), text, style);
+    }
+}
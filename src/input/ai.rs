@@ -0,0 +1,273 @@
+//! CPU opponent, produces the same logical keys as a keyboard.
+
+use crate::{
+    input::keymap::KeyMap,
+    res::sprite::{Player, Status},
+};
+use std::collections::VecDeque;
+
+// decide every N ticks, keep the keys in between
+const THINK_EVERY: u32 = 6;
+
+// distance between the centers where an attack connects
+const REACH: i32 = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Level {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+// chances are in percent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    pub reaction: usize, // ticks
+    pub spacing: i32,    // preferred distance
+    pub aggression: u32,
+    pub anti_air: u32,
+    pub guard: u32,
+    pub combo: u32, // attacks after a hit
+}
+
+// xorshift64*
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng(u64);
+
+// what the CPU has seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct View {
+    distance: i32, // foe - me
+    foe_attacking: bool,
+    foe_airborne: bool,
+    foe_stunned: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ai {
+    pub level: Level,
+    pub params: Params,
+    rng: Rng,
+
+    seen: VecDeque<View>,
+    plan: VecDeque<Vec<KeyMap>>,
+    hold: Vec<KeyMap>,
+    think_timer: u32,
+    guarding: Option<bool>, // decided once per attack of the foe
+}
+
+////////////////////////////////////////
+impl Level {
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Level::Easy => Some(Level::Normal),
+            Level::Normal => Some(Level::Hard),
+            Level::Hard => None,
+        }
+    }
+
+    pub fn params(&self) -> Params {
+        match self {
+            Level::Easy => Params {
+                reaction: 30,
+                spacing: 280,
+                aggression: 15,
+                anti_air: 10,
+                guard: 20,
+                combo: 1,
+            },
+
+            Level::Normal => Params {
+                reaction: 18,
+                spacing: 240,
+                aggression: 30,
+                anti_air: 40,
+                guard: 50,
+                combo: 2,
+            },
+
+            Level::Hard => Params {
+                reaction: 8,
+                spacing: 210,
+                aggression: 50,
+                anti_air: 80,
+                guard: 85,
+                combo: 3,
+            },
+        }
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // 0 is a fixed point
+        Self(seed.max(1))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        (self.0.wrapping_mul(0x2545f4914f6cdd1d) >> 32) as u32
+    }
+
+    #[inline(always)]
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.next_u32() % 100 < percent
+    }
+}
+
+impl Ai {
+    pub fn new(level: Level, seed: u64) -> Self {
+        Self {
+            level,
+            params: level.params(),
+            rng: Rng::new(seed),
+            seen: VecDeque::new(),
+            plan: VecDeque::new(),
+            hold: vec![],
+            think_timer: 0,
+            guarding: None,
+        }
+    }
+
+    // keys for this tick
+    pub fn think(&mut self, me: &Player, foe: &Player) -> Vec<KeyMap> {
+        self.seen.push_back(View {
+            distance: foe.center() as i32 - me.center() as i32,
+            foe_attacking: foe.is_attacking(),
+            foe_airborne: foe.y_offset > 0,
+            foe_stunned: matches!(foe.status, Status::Hit(_)),
+        });
+
+        // reaction time
+        if self.seen.len() <= self.params.reaction {
+            return vec![];
+        }
+
+        let view = self.seen.pop_front().unwrap();
+
+        if let Some(keys) = self.plan.pop_front() {
+            return keys;
+        }
+
+        let (toward, back) = if view.distance > 0 {
+            (KeyMap::Right, KeyMap::Left)
+        } else {
+            (KeyMap::Left, KeyMap::Right)
+        };
+        let distance = view.distance.abs();
+
+        // guard
+        if !view.foe_attacking {
+            self.guarding = None;
+        } else if distance <= REACH + 40 {
+            let guard = self.params.guard;
+            let guarding = *self.guarding.get_or_insert_with(|| self.rng.chance(guard));
+
+            if guarding {
+                return vec![back];
+            }
+        }
+
+        if !me.is_actionable() {
+            return vec![];
+        }
+
+        // combo
+        if view.foe_stunned && distance <= REACH {
+            // mash, every other tick
+            for idx in 0..self.params.combo * me.attack.total() {
+                self.plan.push_back(if idx % 2 == 0 {
+                    vec![]
+                } else {
+                    vec![KeyMap::Att]
+                });
+            }
+
+            return vec![KeyMap::Att];
+        }
+
+        // anti-air
+        if view.foe_airborne && distance <= REACH && self.rng.chance(self.params.anti_air) {
+            return vec![KeyMap::Att];
+        }
+
+        if self.think_timer > 0 {
+            self.think_timer -= 1;
+            return self.hold.clone();
+        }
+
+        self.think_timer = THINK_EVERY;
+        self.hold = if distance <= REACH && self.rng.chance(self.params.aggression) {
+            vec![KeyMap::Att]
+        } else if distance > self.params.spacing + 40 {
+            vec![toward]
+        } else if distance < self.params.spacing - 80 {
+            vec![back]
+        } else {
+            vec![]
+        };
+
+        self.hold.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::sprite::{Dire, Packet};
+
+    // (me, foe) with their centers `distance` apart, an empty animation each
+    fn players(distances: &[u32]) -> Vec<(Player, Player)> {
+        let mut me = Player::new(Dire::Right, true);
+        me.stream.push(Packet::default());
+        let mut foe = Player::new(Dire::Left, false);
+        foe.stream.push(Packet::default());
+
+        distances
+            .iter()
+            .map(|distance| {
+                me.reset(500, Dire::Right);
+                foe.reset(500 + distance, Dire::Left);
+                (me.clone(), foe.clone())
+            })
+            .collect()
+    }
+
+    // 100 ticks at each distance
+    fn run(mut ai: Ai, pairs: &[(Player, Player)]) -> Vec<Vec<KeyMap>> {
+        pairs
+            .iter()
+            .flat_map(|(me, foe)| (0..100).map(|_| ai.think(me, foe)).collect::<Vec<_>>())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_keys() {
+        let pairs = players(&[150, 260, 400, 120]);
+
+        for level in [Level::Easy, Level::Normal, Level::Hard] {
+            let a = run(Ai::new(level, 42), &pairs);
+            let b = run(Ai::new(level, 42), &pairs);
+
+            assert_eq!(a, b, "{:?}", level);
+            assert!(a.iter().any(|keys| !keys.is_empty()), "{:?}", level);
+        }
+    }
+
+    #[test]
+    fn levels_differ() {
+        let pairs = players(&[150, 260, 400, 120]);
+
+        let easy = run(Ai::new(Level::Easy, 42), &pairs);
+        let normal = run(Ai::new(Level::Normal, 42), &pairs);
+        let hard = run(Ai::new(Level::Hard, 42), &pairs);
+
+        assert_ne!(easy, normal);
+        assert_ne!(normal, hard);
+        assert_ne!(easy, hard);
+    }
+}
@@ -1 +1,72 @@
-pub enum KeyMap {}
+// TODO:
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyMap {
+    Quit = -1,
+    Unknown = 0,
+
+    Left = 1,
+    Right = 2,
+
+    Up = 3,
+    Down = 4,
+
+    Att = 11,
+}
+
+impl KeyMap {
+    pub fn from_key_list(list: &[minifb::Key]) -> Vec<Self> {
+        let mut res = Vec::with_capacity(list.len());
+
+        for key in list.iter() {
+            res.push(Self::from(key));
+        }
+
+        res
+    }
+}
+
+impl From<&minifb::Key> for KeyMap {
+    fn from(value: &minifb::Key) -> Self {
+        use minifb::Key;
+
+        match *value {
+            // p1
+            Key::W => Self::Up,
+            Key::S => Self::Down,
+            Key::A => Self::Left,
+            Key::D => Self::Right,
+            Key::J => Self::Att,
+            // p2
+            Key::Up => Self::Up,
+            Key::Down => Self::Down,
+            Key::Left => Self::Left,
+            Key::Right => Self::Right,
+            Key::NumPad1 => Self::Att,
+
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Into<minifb::Key> for &KeyMap {
+    fn into(self) -> minifb::Key {
+        use minifb::Key;
+
+        match self {
+            // p1
+            KeyMap::Up => Key::W,
+            KeyMap::Down => Key::S,
+            KeyMap::Left => Key::A,
+            KeyMap::Right => Key::D,
+            KeyMap::Att => Key::J,
+            // p2
+            KeyMap::Up => Key::Up,
+            KeyMap::Down => Key::Down,
+            KeyMap::Left => Key::Left,
+            KeyMap::Right => Key::Right,
+            KeyMap::Att => Key::NumPad1,
+
+            _ => Key::Unknown,
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod audio;
+pub mod game;
+pub mod input;
 pub mod res;
 pub mod window;
 
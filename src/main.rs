@@ -1,8 +1,32 @@
 use kuma::{
-    res::sprite::{Dire, Player},
+    audio::{
+        backend::Null,
+        mixer::{Group, Mixer, RATE},
+    },
+    game::{
+        round::{Match, ROUND_TIME},
+        sim::Game,
+        training::Training,
+    },
+    input::ai::{Ai, Level},
+    res::{
+        palette::{pick_colors, ColorSet},
+        sprite::{Dire, Player},
+        stage::Stage,
+        watch::Watcher,
+    },
+    window::{
+        camera::Camera,
+        canvas::Canvas,
+        effects::Effects,
+        hud::Hud,
+        overlay::Overlay,
+        render::{Draw, Layer, RenderQueue},
+    },
     FPS,
 };
-use minifb::Window;
+use minifb::{Key, KeyRepeat, Window};
+use std::path::Path;
 use std::thread::sleep_ms;
 
 fn main() {
@@ -22,39 +46,199 @@ fn main() {
     let mut window = Window::new("kuma", width, height, windowoptions).unwrap();
 
     // ==========================================
+    // character select: kuma [p1 colour] [p2 colour]
+    let picks: Vec<usize> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse().unwrap_or(0))
+        .collect();
+    let colors = ColorSet::load(Path::new("./tests"), "kuma").unwrap_or_else(|e| {
+        log::warn!("{}", e);
+        ColorSet::new("kuma")
+    });
+    let (c1, c2) = pick_colors(
+        ("kuma", picks.first().copied().unwrap_or(0)),
+        ("kuma", picks.get(1).copied().unwrap_or(0)),
+        colors.len(),
+    );
+
     // p1
     let mut p1 = Player::new(Dire::Right, true);
-    p1.load_stream("stop");
-    p1.load_stream("walk");
-    p1.load_stream("run");
+    p1.colors = colors.colors(c1);
+    p1.load_chara("kuma");
     p1.x_offset = 0;
     p1.y_offset = 0;
 
     // p2
-    let mut p2 = p1.clone();
-    p1.is_p1 = false;
-    p2.dire = Dire::Left;
+    let mut p2 = Player::new(Dire::Left, false);
+    p2.colors = colors.colors(c2);
+    p2.load_chara("kuma");
     p2.x_offset = width as u32 - 300;
 
     // ==========================================
     // init
     window.update();
-    let bg = &vec![123 * 123 * 123; width * height]; // background
-    let mut buffer = bg.clone();
+    let mut stage = if Path::new("./tests/stage.ase").exists() {
+        Stage::from_ase(Path::new("./tests/stage.ase"))
+    } else {
+        Stage::flat(width as u32, height as u32, 123 * 123 * 123) // background
+    };
+    let mut camera = Camera::new(width as u32, height as u32);
+    let mut buffer = vec![0; width * height];
+    let mut hud = Hud::new(width as u32, height as u32, ["P1", "P2"]);
+    let mut overlay = Overlay::new();
+    let mut effects = Effects::load(Path::new("./tests"));
+    let mut mixer = Mixer::load(Path::new("./tests"), RATE);
+    let mut speaker = Null;
+    if let Some(music) = stage.music.as_ref() {
+        mixer.play_music(music);
+    }
+    let mut training: Option<Training> = None;
+    let mut cpu: Option<Ai> = None;
+    let mut watcher: Option<Watcher> = None;
+    p1.stage_width = stage.width;
+    p2.stage_width = stage.width;
+    let mut game = Game::new(p1, p2);
+    let mut round = Match::new(hud.rounds * 2 - 1, Some(ROUND_TIME));
+    round.start(&mut game, stage.width);
     // ==========================================
     // display
     'l1: while window.is_open() {
-        buffer = bg.clone();
+        let mut k1 = game.p1.read_keys(&window);
+        let mut k2 = game.p2.read_keys(&window);
+
+        // rematch
+        if round.is_over() && window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            round = Match::new(round.best_of, round.round_time);
+            round.start(&mut game, stage.width);
+        }
+
+        // training, F2
+        if window.is_key_pressed(Key::F2, KeyRepeat::No) {
+            training = match training {
+                Some(_) => None,
+                None => Some(Training::new()),
+            };
+        }
+
+        // cpu, F6: easy -> normal -> hard -> off
+        if window.is_key_pressed(Key::F6, KeyRepeat::No) {
+            cpu = match cpu.as_ref().map(|ai| ai.level) {
+                None => Some(Level::Easy),
+                Some(level) => level.next(),
+            }
+            .map(|level| Ai::new(level, 0x6b756d61));
+        }
+
+        // hot reload of ./tests, F9
+        if window.is_key_pressed(Key::F9, KeyRepeat::No) {
+            watcher = match watcher {
+                Some(_) => None,
+                None => Some(Watcher::new(Path::new("./tests"))),
+            };
+        }
+
+        if let Some(watcher) = watcher.as_mut() {
+            for path in watcher.poll() {
+                game.reload(&path);
+            }
+        }
+
+        if let Some(training) = training.as_mut() {
+            training.check_keys(&window, &mut game, stage.width);
+            k2 = training.dummy_input(&game, &k2);
+        } else if let Some(ai) = cpu.as_mut() {
+            k2 = ai.think(&game.p2, &game.p1);
+        }
+
+        // no rounds in training
+        if training.is_none() && !round.can_fight() {
+            k1.clear();
+            k2.clear();
+        }
+
+        // p1, p2
+        game.update(&k1, &k2);
+
+        if let Some(training) = training.as_mut() {
+            training.update(&mut game);
+        } else {
+            round.update(&mut game, stage.width);
+        }
+
+        effects.observe(&game);
+        effects.update();
+
+        // sound
+        for name in game.sounds.iter() {
+            mixer.play(name, Group::Sfx);
+        }
+        for hit in game.hits.iter() {
+            mixer.play(if hit.blocked { "guard" } else { "hit" }, Group::Sfx);
+        }
+        mixer.tick(&mut speaker);
+
+        let (p1, p2) = (&game.p1, &game.p2);
+
+        // stage
+        stage.next_frame();
+        for hit in game.hits.iter().filter(|hit| !hit.blocked) {
+            camera.shake(hit.damage);
+        }
+        camera.follow(p1.center(), p2.center(), stage.width);
+
+        // debug
+        overlay.check_keys(&window);
+
+        // hud
+        hud.update(p1, p2);
+        hud.timer = round.seconds();
+        hud.wins = round.wins;
+
+        // draw
+        let mut queue = RenderQueue::new();
+        stage.render(&mut queue, &camera);
+        if game.freeze > 0 {
+            // super flash, the stage goes dark
+            let (width, height) = (width as u32, height as u32);
+            let argb = 0xa0000000;
+            queue.push(
+                Layer::Stage,
+                i32::MAX,
+                0,
+                0,
+                Draw::Rect {
+                    width,
+                    height,
+                    argb,
+                },
+            );
+        }
+        p1.render(&mut queue, &camera, stage.ground);
+        p2.render(&mut queue, &camera, stage.ground);
+        for e in game.entities.iter() {
+            e.render(&mut queue, &camera, stage.ground, game.players()[e.owner]);
+        }
+        effects.render(
+            &mut queue,
+            &camera,
+            stage.ground,
+            (width as u32, height as u32),
+        );
+        hud.render(&mut queue);
+        overlay.render(&mut queue, &camera, &stage, &[p1, p2], &game.entities);
 
-        // p1
-        p1.check_keys(&window);
-        p1.next_frame();
-        p1.flush_buffer(&mut buffer, 0, width as u32);
+        game.combos[0].render(&mut queue, &hud.font, (40, 140), false);
+        game.combos[1].render(&mut queue, &hud.font, (width as i32 - 40, 140), true);
 
-        // p2
-        p2.check_keys(&window);
-        p2.next_frame();
-        p2.flush_buffer(&mut buffer, 0, width as u32);
+        if let Some(training) = training.as_ref() {
+            training.render(&mut queue, &hud.font);
+        } else {
+            round.render(&mut queue, &hud.font, &hud.names, (hud.width, hud.height));
+        }
+        // the shaken stage does not cover the edges
+        let mut canvas = Canvas::new(&mut buffer, width as u32);
+        canvas.clear(0xff000000);
+        queue.flush(&mut canvas);
 
         window.update_with_buffer(&buffer, width, height).unwrap();
 
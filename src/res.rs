@@ -1 +1,10 @@
+pub mod atlas;
+pub mod chara;
+pub mod event;
+pub mod font;
+pub mod json;
+pub mod palette;
+pub mod sheet;
 pub mod sprite;
+pub mod stage;
+pub mod watch;
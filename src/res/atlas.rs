@@ -0,0 +1,226 @@
+//! Frames trimmed to their visible pixels and packed into pages, identical frames once.
+
+use crate::window::render::Draw;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// px, a frame wider than this gets a page of its own
+const PAGE_WIDTH: u32 = 1024;
+const PAGE_HEIGHT: u32 = 1024;
+
+// a frame in a page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Region {
+    pub page: usize,
+    pub x: u32, // in the page
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub offset: (u32, u32), // of the trimmed frame, in the canvas of the frame
+}
+
+// rows are added while packing, up to PAGE_HEIGHT
+#[derive(Debug, Clone)]
+struct Page {
+    data: Vec<u32>, // Vec<argb>
+    width: u32,
+    height: u32,
+    shelf: (u32, u32, u32), // x, y, height of the row being filled
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub frames: usize,
+    pub unique: usize,
+    pub pages: usize,
+    pub full: usize,   // bytes, untrimmed and stored for both sides
+    pub packed: usize, // bytes of the pages
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Atlas {
+    pages: Vec<Page>,
+    hashes: Vec<(u64, Region)>, // of the unique frames
+    stats: Stats,
+}
+
+////////////////////////////////////////
+impl Region {
+    // top left in the canvas of the frame, mirrored if `flip`
+    #[inline(always)]
+    pub fn origin(&self, canvas_width: u32, flip: bool) -> (i32, i32) {
+        let x = if flip {
+            canvas_width as i32 - (self.offset.0 + self.width) as i32
+        } else {
+            self.offset.0 as i32
+        };
+
+        (x, self.offset.1 as i32)
+    }
+}
+
+impl Page {
+    fn new(width: u32) -> Self {
+        Self {
+            data: vec![],
+            width,
+            height: 0,
+            shelf: (0, 0, 0),
+        }
+    }
+
+    // (x, y), None: full
+    fn place(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (mut x, mut y, mut row) = self.shelf;
+
+        // next row
+        if x + width > self.width {
+            (x, y, row) = (0, y + row, 0);
+        }
+
+        if y + height > PAGE_HEIGHT.max(height) || width > self.width {
+            return None;
+        }
+
+        self.shelf = (x + width, y, row.max(height));
+
+        if y + height > self.height {
+            self.height = y + height;
+            self.data.resize((self.width * self.height) as usize, 0);
+        }
+
+        Some((x, y))
+    }
+}
+
+impl Atlas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a full frame of `width`, trimmed and packed
+    pub fn add(&mut self, frame: &[u32], width: u32) -> Region {
+        let height = (frame.len() as u32).checked_div(width).unwrap_or(0);
+        let (x0, y0, x1, y1) = trim(frame, width, height);
+        let (w, h) = (x1 - x0, y1 - y0);
+
+        let rows = (y0..y1).map(|y| &frame[(y * width + x0) as usize..(y * width + x1) as usize]);
+
+        let mut hasher = DefaultHasher::new();
+        (w, h).hash(&mut hasher);
+        rows.clone().for_each(|row| row.hash(&mut hasher));
+        let hash = hasher.finish();
+
+        self.stats.frames += 1;
+        self.stats.full += frame.len() * 4 * 2;
+
+        // the pixels are compared, not only the hashes
+        let same = self.hashes.iter().find(|(other, r)| {
+            *other == hash && (r.width, r.height) == (w, h) && self.rows(r).eq(rows.clone())
+        });
+
+        if let Some((_, region)) = same {
+            return Region {
+                offset: (x0, y0),
+                ..*region
+            };
+        }
+
+        let (page, (x, y)) = self.place(w, h);
+        let region = Region {
+            page,
+            x,
+            y,
+            width: w,
+            height: h,
+            offset: (x0, y0),
+        };
+
+        let p = &mut self.pages[page];
+        for (idx, row) in rows.enumerate() {
+            let at = ((y + idx as u32) * p.width + x) as usize;
+            p.data[at..at + row.len()].copy_from_slice(row);
+        }
+
+        self.hashes.push((hash, region));
+        self.stats.unique += 1;
+
+        region
+    }
+
+    fn place(&mut self, width: u32, height: u32) -> (usize, (u32, u32)) {
+        if let Some(at) = self.pages.last_mut().and_then(|p| p.place(width, height)) {
+            return (self.pages.len() - 1, at);
+        }
+
+        let mut page = Page::new(PAGE_WIDTH.max(width));
+        let at = page.place(width, height).unwrap();
+        self.pages.push(page);
+
+        (self.pages.len() - 1, at)
+    }
+
+    fn rows<'a>(&'a self, r: &Region) -> impl Iterator<Item = &'a [u32]> + Clone {
+        let p = &self.pages[r.page];
+        let (x, w) = (r.x as usize, r.width as usize);
+
+        (r.y..r.y + r.height).map(move |y| {
+            let at = (y * p.width) as usize + x;
+            &p.data[at..at + w]
+        })
+    }
+
+    #[inline(always)]
+    pub fn draw(&self, r: &Region, flip: bool) -> Draw<'_> {
+        match self.pages.get(r.page) {
+            Some(p) => Draw::Region {
+                data: &p.data,
+                stride: p.width,
+                rect: (r.x, r.y, r.width, r.height),
+                flip,
+            },
+
+            // nothing visible
+            None => Draw::Region {
+                data: &[],
+                stride: 0,
+                rect: (0, 0, 0, 0),
+                flip,
+            },
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            pages: self.pages.len(),
+            packed: self.pages.iter().map(|p| p.data.len() * 4).sum(),
+            ..self.stats
+        }
+    }
+}
+
+////////////////////////////////////////
+// (x0, y0, x1, y1) of the visible pixels, all 0: nothing visible
+fn trim(frame: &[u32], width: u32, height: u32) -> (u32, u32, u32, u32) {
+    let (mut x0, mut y0, mut x1, mut y1) = (width, height, 0, 0);
+
+    for y in 0..height {
+        let row = &frame[(y * width) as usize..((y + 1) * width) as usize];
+
+        let Some(first) = row.iter().position(|argb| argb >> 24 != 0) else {
+            continue;
+        };
+        let last = row.iter().rposition(|argb| argb >> 24 != 0).unwrap();
+
+        x0 = x0.min(first as u32);
+        x1 = x1.max(last as u32 + 1);
+        y0 = y0.min(y);
+        y1 = y + 1;
+    }
+
+    if x0 >= x1 {
+        return (0, 0, 0, 0);
+    }
+
+    (x0, y0, x1, y1)
+}
@@ -0,0 +1,813 @@
+//! Character data: states and moves, read from ./tests/{id}.chr.
+
+use crate::{
+    game::{
+        combat::{Armor, Attack, JAB},
+        knockdown::{Bounce, Knockdown},
+        state::State,
+    },
+    input::keymap::KeyMap,
+    res::sprite::{Dire, Speed, EP_MAX, EP_START},
+};
+use std::fs;
+use std::path::Path;
+
+const COUNTER: Bonus = Bonus {
+    hitstun: 6,
+    damage: 120,
+};
+const PUNISH: Bonus = Bonus {
+    hitstun: 10,
+    damage: 120,
+};
+
+// directions are relative to the facing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Forward,
+    Back,
+    Up,
+    Down,
+    Att,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Kind {
+    #[default]
+    Normal,
+    Special,
+    Super,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Command {
+    pub presses: Vec<Input>, // in order, the last one starts the move
+    pub with: Vec<Input>,    // held OR pressed with the last one: F+A
+}
+
+// a state (stop, walk ...) or a move (has an attack)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub name: String,
+    pub anim: String,       // file OR file:tag
+    pub ticks: Option<u32>, // per animation frame, None: the durations of the art
+    pub velocity: i32,      // px per tick, forward
+    pub reverse: bool,      // animation played backwards
+
+    pub attack: Option<Attack>,
+    pub kind: Kind,
+    pub cancel: Vec<String>, // move names, kinds OR jump; on hit or block
+    pub window: Option<(u32, u32)>, // frames of the move, default: active to the end
+    pub command: Command,
+    pub spawn: Option<(String, u32)>, // projectile, at frame
+    pub sounds: Vec<(String, u32)>,   // at frame
+    pub cost: u32,                    // EP, EX moves and supers
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projectile {
+    pub name: String,
+    pub anim: String,
+    pub ticks: u32,
+    pub velocity: i32, // px per tick, forward
+    pub life: u32,     // ticks
+
+    pub size: (u32, u32),   // hitbox, around the center
+    pub offset: (u32, u32), // from the owner: forward, height
+    pub limit: u32,         // on screen, per owner
+    pub durability: u32,    // clashes before it is destroyed
+
+    pub attack: Attack, // frame data is not used
+}
+
+// EP rules of a character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Meter {
+    pub max: u32,
+    pub levels: u32, // bars of the gauge
+    pub start: u32,  // of each round
+
+    // gained
+    pub hit: u32, // by the attacker, half on block
+    pub hurt: u32,
+    pub whiff: u32, // a move that did not connect
+}
+
+// extra, on a counter OR punish counter hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bonus {
+    pub hitstun: u32,
+    pub damage: u32, // percent
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharData {
+    pub name: String,
+    pub meter: Meter,
+    pub counter: Bonus, // of the attacker
+    pub punish: Bonus,
+    pub states: Vec<Move>,
+    pub moves: Vec<Move>, // longest command first
+    pub projectiles: Vec<Projectile>,
+}
+
+////////////////////////////////////////
+impl Input {
+    pub fn from_key(key: KeyMap, dire: Dire) -> Option<Self> {
+        match (key, dire) {
+            (KeyMap::Up, _) => Some(Input::Up),
+            (KeyMap::Down, _) => Some(Input::Down),
+            (KeyMap::Att, _) => Some(Input::Att),
+            (KeyMap::Left, Dire::Left) | (KeyMap::Right, Dire::Right) => Some(Input::Forward),
+            (KeyMap::Left, _) | (KeyMap::Right, _) => Some(Input::Back),
+
+            _ => None,
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "F" => Some(Input::Forward),
+            "B" => Some(Input::Back),
+            "U" => Some(Input::Up),
+            "D" => Some(Input::Down),
+            "A" => Some(Input::Att),
+
+            _ => None,
+        }
+    }
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Normal => "normal",
+            Kind::Special => "special",
+            Kind::Super => "super",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "normal" => Some(Kind::Normal),
+            "special" => Some(Kind::Special),
+            "super" => Some(Kind::Super),
+
+            _ => None,
+        }
+    }
+}
+
+impl Command {
+    // presses: oldest first, (input, age in ticks)
+    // holds: held in this tick
+    pub fn matched(
+        &self,
+        presses: &[(Input, u32)],
+        buffer: u32,
+        holds: impl Fn(Input) -> bool,
+    ) -> bool {
+        let Some((last, rest)) = self.presses.split_last() else {
+            return false;
+        };
+
+        // not a press long ago, as when walking into a jab
+        let together = self.with.iter().all(|with| {
+            holds(*with)
+                || presses
+                    .iter()
+                    .any(|(input, age)| input == with && *age <= buffer)
+        });
+
+        if !together {
+            return false;
+        }
+
+        // the last press, may be buffered a few ticks
+        let Some(end) = presses
+            .iter()
+            .rposition(|(input, age)| input == last && *age <= buffer)
+        else {
+            return false;
+        };
+
+        let mut need = rest.iter().rev().peekable();
+
+        for (input, _) in presses[..end].iter().rev() {
+            if need.peek() == Some(&input) {
+                need.next();
+            }
+        }
+
+        need.peek().is_none()
+    }
+}
+
+impl Move {
+    pub fn new(name: &str, anim: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            anim: anim.to_string(),
+            ticks: None,
+            velocity: 0,
+            reverse: false,
+            attack: None,
+            kind: Kind::Normal,
+            cancel: vec![],
+            window: None,
+            command: Command::default(),
+            spawn: None,
+            sounds: vec![],
+            cost: 0,
+        }
+    }
+
+    // into `other`, by name OR kind
+    #[inline(always)]
+    pub fn can_cancel(&self, other: &Move) -> bool {
+        self.cancel
+            .iter()
+            .any(|c| *c == other.name || c == other.kind.name())
+    }
+
+    #[inline(always)]
+    pub fn can_jump_cancel(&self) -> bool {
+        self.cancel.iter().any(|c| c == "jump")
+    }
+
+    // frame 0 is the first frame of the move
+    pub fn in_window(&self, frame: u32) -> bool {
+        let attack = self.attack.unwrap_or_default();
+        let (from, to) = self
+            .window
+            .unwrap_or((attack.startup.saturating_sub(1), attack.total()));
+
+        from <= frame && frame < to
+    }
+}
+
+impl Projectile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            anim: name.to_string(),
+            ticks: Speed::Norminal as u32,
+            velocity: 8,
+            life: 120,
+            size: (60, 60),
+            offset: (100, 200),
+            limit: 1,
+            durability: 1,
+            attack: JAB,
+        }
+    }
+}
+
+impl Meter {
+    // EP per bar
+    #[inline(always)]
+    pub fn level_size(&self) -> u32 {
+        self.max / self.levels.max(1)
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self {
+            max: EP_MAX,
+            levels: 3,
+            start: EP_START,
+            hit: 20,
+            hurt: 10,
+            whiff: 4,
+        }
+    }
+}
+
+impl CharData {
+    // data:
+    //
+    // # comment
+    // name KUMA
+    // ep_max 300
+    // ep_levels 3
+    // ep_start 100
+    // ep_hit 20             gained on hit, half on block
+    // ep_hurt 10            gained when hit
+    // ep_whiff 4            gained when a move did not connect
+    // counter 6 120         extra hitstun, damage percent: hit in the startup
+    // punish 10 120         in the recovery
+    //
+    // state walk_fwd      see State::name()
+    // anim walk             (OR all:walk)
+    // ticks 6               per animation frame, up to 255, default: the durations of the art
+    // velocity 4
+    // reverse               play the animation backwards
+    //
+    // move jab
+    // anim stop
+    // input A               F B U D A, e.g. D F A, OR F+A: F held with the last press
+    // frames 4 3 8          startup active recovery
+    // damage 50
+    // hitstun 14
+    // blockstun 10
+    // hitstop 6             ticks both players freeze on contact
+    // kind normal           normal special super
+    // cancel straight       names, kinds OR jump
+    // window 3 10           frames of the move to cancel in
+    // knockdown             OR knockdown hard, no quick-rise
+    // launch 4 18           px per tick: back, up
+    // juggle 2              points, JUGGLE_LIMIT in the air
+    // bounce wall           OR ground, once after the launch
+    // invincible strike 0 8 OR throw, projectile: frames of the move
+    // armor 1 0 6           hits absorbed, frames of the move
+    // throw 80              range of the throw box, not a strike
+    // spawn fireball 8      projectile at frame 8
+    // sound swing 2         ./tests/swing.wav at frame 2, one line each
+    // cost 100              EP, spent when the move starts
+    //
+    // projectile fireball
+    // anim all:run
+    // velocity 8
+    // life 120              ticks
+    // size 60 60            hitbox
+    // offset 100 200        from the owner: forward, height
+    // limit 1               on screen
+    // durability 1
+    // damage 60             hitstun blockstun knockdown
+    //
+    // Err: the file and the line
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut data = Self {
+            name: String::new(),
+            meter: Meter::default(),
+            counter: COUNTER,
+            punish: PUNISH,
+            states: vec![],
+            moves: vec![],
+            projectiles: vec![],
+        };
+
+        let mut is_move = false;
+        let mut is_projectile = false;
+
+        for (idx, line) in text.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let nums: Vec<i32> = value
+                .split_whitespace()
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            let num = nums.first().copied();
+
+            match key {
+                "name" => {
+                    data.name = value.to_string();
+                    continue;
+                }
+                "ep_max" | "ep_levels" | "ep_start" | "ep_hit" | "ep_hurt" | "ep_whiff" => {
+                    let Some(n) = num.filter(|n| *n >= 0).map(|n| n as u32) else {
+                        return Err(bad_line(path, idx, line));
+                    };
+                    let meter = &mut data.meter;
+
+                    match key {
+                        "ep_max" => meter.max = n,
+                        "ep_levels" => meter.levels = n,
+                        "ep_start" => meter.start = n,
+                        "ep_hit" => meter.hit = n,
+                        "ep_hurt" => meter.hurt = n,
+                        _ => meter.whiff = n,
+                    }
+                    continue;
+                }
+                "counter" | "punish" => {
+                    let [hitstun, damage] = nums[..] else {
+                        return Err(bad_line(path, idx, line));
+                    };
+                    if hitstun < 0 || damage < 0 {
+                        return Err(bad_line(path, idx, line));
+                    }
+                    let bonus = Bonus {
+                        hitstun: hitstun as u32,
+                        damage: damage as u32,
+                    };
+
+                    if key == "counter" {
+                        data.counter = bonus;
+                    } else {
+                        data.punish = bonus;
+                    }
+                    continue;
+                }
+                "projectile" => {
+                    is_projectile = true;
+                    data.projectiles.push(Projectile::new(value));
+                    continue;
+                }
+                "state" | "move" => {
+                    is_move = key == "move";
+                    is_projectile = false;
+
+                    let mut new = Move::new(value, value);
+                    if is_move {
+                        new.attack = Some(JAB);
+                        data.moves.push(new);
+                    } else {
+                        data.states.push(new);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let (true, Some(last)) = (is_projectile, data.projectiles.last_mut()) {
+                // only velocity and launch are signed
+                let n = num.filter(|n| *n >= 0).map(|n| n as u32);
+
+                match (key, n) {
+                    ("anim", _) => last.anim = value.to_string(),
+                    ("ticks", Some(n)) => last.ticks = n,
+                    ("velocity", _) if nums.len() == 1 => last.velocity = nums[0],
+                    ("life", Some(n)) => last.life = n,
+                    ("size", Some(n)) if nums.len() == 2 && nums[1] >= 0 => {
+                        last.size = (n, nums[1] as u32)
+                    }
+                    ("offset", Some(n)) if nums.len() == 2 && nums[1] >= 0 => {
+                        last.offset = (n, nums[1] as u32)
+                    }
+                    ("limit", Some(n)) => last.limit = n,
+                    ("durability", Some(n)) => last.durability = n,
+                    ("damage", Some(n)) => last.attack.damage = n,
+                    ("hitstun", Some(n)) => last.attack.hitstun = n,
+                    ("blockstun", Some(n)) => last.attack.blockstun = n,
+                    ("hitstop", Some(n)) => last.attack.hitstop = n,
+                    ("knockdown", None) => {
+                        last.attack.knockdown =
+                            Knockdown::parse(value).ok_or_else(|| bad_line(path, idx, line))?
+                    }
+                    ("launch", _) if nums.len() == 2 => last.attack.launch = (nums[0], nums[1]),
+                    ("juggle", Some(n)) => last.attack.juggle = n,
+
+                    _ => return Err(bad_line(path, idx, line)),
+                }
+                continue;
+            }
+
+            let Some(last) = (if is_move {
+                data.moves.last_mut()
+            } else {
+                data.states.last_mut()
+            }) else {
+                // before the first state or move
+                return Err(bad_line(path, idx, line));
+            };
+
+            match (key, num) {
+                ("anim", _) => last.anim = value.to_string(),
+                ("ticks", Some(n)) if (0..=u8::MAX as i32).contains(&n) => {
+                    last.ticks = Some(n as u32)
+                }
+                ("velocity", Some(n)) => last.velocity = n,
+                ("reverse", None) => last.reverse = true,
+                ("kind", None) if is_move => {
+                    last.kind = Kind::parse(value).ok_or_else(|| bad_line(path, idx, line))?
+                }
+                ("window", Some(n)) if is_move && nums.len() == 2 && n >= 0 && nums[1] >= n => {
+                    last.window = Some((n as u32, nums[1] as u32));
+                }
+                ("knockdown", None) if is_move => {
+                    last.attack.as_mut().unwrap().knockdown =
+                        Knockdown::parse(value).ok_or_else(|| bad_line(path, idx, line))?
+                }
+                ("bounce", None) if is_move => {
+                    last.attack.as_mut().unwrap().bounce =
+                        Bounce::parse(value).ok_or_else(|| bad_line(path, idx, line))?
+                }
+                ("launch", Some(_)) if is_move && nums.len() == 2 => {
+                    last.attack.as_mut().unwrap().launch = (nums[0], nums[1]);
+                }
+                ("invincible", Some(n)) if is_move && nums.len() == 2 && n >= 0 && nums[1] >= 0 => {
+                    let inv = &mut last.attack.as_mut().unwrap().invincible;
+                    let frames = Some((n as u32, nums[1] as u32));
+
+                    match value.split_whitespace().next() {
+                        Some("strike") => inv.strike = frames,
+                        Some("throw") => inv.throw = frames,
+                        Some("projectile") => inv.projectile = frames,
+                        _ => return Err(bad_line(path, idx, line)),
+                    }
+                }
+                ("armor", Some(n))
+                    if is_move && nums.len() == 3 && nums.iter().all(|n| *n >= 0) =>
+                {
+                    last.attack.as_mut().unwrap().armor = Some(Armor {
+                        hits: n as u32,
+                        from: nums[1] as u32,
+                        to: nums[2] as u32,
+                    });
+                }
+                ("spawn", _) if is_move && nums.len() == 1 => {
+                    let name = value.split_whitespace().next().unwrap();
+                    last.spawn = Some((name.to_string(), nums[0].max(0) as u32));
+                }
+                ("sound", _) if is_move && nums.len() == 1 => {
+                    let name = value.split_whitespace().next().unwrap();
+                    last.sounds.push((name.to_string(), nums[0].max(0) as u32));
+                }
+                ("cancel", _) => last.cancel = value.split_whitespace().map(String::from).collect(),
+                ("input", _) => {
+                    let parse = |k| Input::parse(k).ok_or_else(|| bad_line(path, idx, line));
+                    let mut keys: Vec<&str> = value.split_whitespace().collect();
+                    let mut with: Vec<&str> = keys.pop().unwrap_or_default().split('+').collect();
+                    keys.extend(with.pop());
+
+                    last.command = Command {
+                        presses: keys.into_iter().map(parse).collect::<Result<_, _>>()?,
+                        with: with.into_iter().map(parse).collect::<Result<_, _>>()?,
+                    };
+                }
+
+                (_, Some(n)) if is_move && n >= 0 => {
+                    let attack = last.attack.as_mut().unwrap();
+                    let n = n as u32;
+
+                    match key {
+                        "frames" if nums.len() == 3 && nums.iter().all(|n| *n >= 0) => {
+                            attack.startup = n;
+                            attack.active = nums[1] as u32;
+                            attack.recovery = nums[2] as u32;
+                        }
+                        "damage" => attack.damage = n,
+                        "hitstun" => attack.hitstun = n,
+                        "blockstun" => attack.blockstun = n,
+                        "throw" => attack.throw = n,
+                        "hitstop" => attack.hitstop = n,
+                        "juggle" => attack.juggle = n,
+                        "cost" => last.cost = n,
+
+                        _ => return Err(bad_line(path, idx, line)),
+                    }
+                }
+
+                _ => return Err(bad_line(path, idx, line)),
+            }
+        }
+
+        data.moves
+            .sort_by_key(|mv| std::cmp::Reverse(mv.command.presses.len() + mv.command.with.len()));
+
+        log::debug!(
+            "Chara {}: {} states, {} moves, {} projectiles",
+            data.name,
+            data.states.len(),
+            data.moves.len(),
+            data.projectiles.len()
+        );
+
+        Ok(data)
+    }
+
+    // anims: loaded animations, (name, frames)
+    pub fn validate(&self, anims: &[(String, usize)]) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        let anim_error = |name: &str, anim: &str| match anims.iter().find(|(a, _)| a == anim) {
+            None => Some(format!("{}: no animation `{}`", name, anim)),
+            Some((_, 0)) => Some(format!("{}: animation `{}` has no frames", name, anim)),
+            Some(_) => None,
+        };
+
+        for state in State::all().iter().filter(|s| **s != State::Attack) {
+            if self.state(state.name()).is_none() {
+                errors.push(format!("missing state `{}`", state.name()));
+            }
+        }
+
+        for mv in self.states.iter().chain(self.moves.iter()) {
+            errors.extend(anim_error(&mv.name, &mv.anim));
+        }
+
+        let meter = &self.meter;
+        if meter.max == 0 || meter.levels == 0 || meter.start > meter.max {
+            errors.push("ep: max and levels must be > 0, start <= max".to_string());
+        }
+
+        for pr in self.projectiles.iter() {
+            errors.extend(anim_error(&pr.name, &pr.anim));
+
+            if pr.life == 0 || pr.limit == 0 || pr.durability == 0 {
+                errors.push(format!(
+                    "{}: life, limit and durability must be > 0",
+                    pr.name
+                ));
+            }
+        }
+
+        for mv in self.moves.iter() {
+            let attack = mv.attack.unwrap();
+
+            if mv.command.presses.is_empty() {
+                errors.push(format!("{}: no input", mv.name));
+            }
+
+            if attack.startup == 0 || attack.active == 0 {
+                errors.push(format!("{}: startup and active must be > 0", mv.name));
+            }
+
+            for name in mv.cancel.iter() {
+                if self.get_move(name).is_none() && Kind::parse(name).is_none() && name != "jump" {
+                    errors.push(format!("{}: cancel into unknown move `{}`", mv.name, name));
+                }
+            }
+
+            if let Some((name, _)) = &mv.spawn {
+                if self.projectile(name).is_none() {
+                    errors.push(format!("{}: unknown projectile `{}`", mv.name, name));
+                }
+            }
+
+            if mv.cost > self.meter.max {
+                errors.push(format!("{}: costs more than ep_max", mv.name));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // animations used by states and moves, in order
+    pub fn anims(&self) -> Vec<String> {
+        let mut res: Vec<String> = vec![];
+
+        let names = self
+            .states
+            .iter()
+            .chain(self.moves.iter())
+            .map(|mv| &mv.anim);
+
+        for anim in names.chain(self.projectiles.iter().map(|pr| &pr.anim)) {
+            if !res.contains(anim) {
+                res.push(anim.clone());
+            }
+        }
+
+        res
+    }
+
+    #[inline(always)]
+    pub fn state(&self, name: &str) -> Option<&Move> {
+        self.states.iter().find(|mv| mv.name == name)
+    }
+
+    #[inline(always)]
+    pub fn projectile(&self, name: &str) -> Option<&Projectile> {
+        self.projectiles.iter().find(|pr| pr.name == name)
+    }
+
+    #[inline(always)]
+    pub fn get_move(&self, name: &str) -> Option<&Move> {
+        self.moves.iter().find(|mv| mv.name == name)
+    }
+}
+
+// ./tests/{stop,walk,run}.ase with a jab
+impl Default for CharData {
+    fn default() -> Self {
+        let state = |name: &str, anim: &str, velocity: i32| Move {
+            velocity,
+            ..Move::new(name, anim)
+        };
+
+        Self {
+            name: String::new(),
+            meter: Meter::default(),
+            counter: COUNTER,
+            punish: PUNISH,
+            states: vec![
+                state("idle", "stop", 0),
+                state("walk_fwd", "walk", 4),
+                Move {
+                    reverse: true,
+                    ..state("walk_back", "walk", -3)
+                },
+                state("dash", "run", 12),
+                state("run", "run", 16),
+                state("jump", "stop", 4),
+                state("crouch", "stop", 0),
+                state("hitstun", "stop", 0),
+                state("blockstun", "stop", 0),
+                state("air_hit", "stop", 0),
+                state("knockdown", "stop", 0),
+                state("wakeup", "stop", 0),
+                state("thrown", "stop", 0),
+            ],
+            moves: vec![Move {
+                attack: Some(JAB),
+                command: Command {
+                    presses: vec![Input::Att],
+                    with: vec![],
+                },
+                ..Move::new("jab", "stop")
+            }],
+            projectiles: vec![],
+        }
+    }
+}
+
+////////////////////////////////////////
+fn bad_line(path: &Path, idx: usize, line: &str) -> String {
+    format!("{}:{}: bad line `{}`", path.display(), idx + 1, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // {name}.chr in the temp dir
+    fn open_text(name: &str, text: &str) -> Result<CharData, String> {
+        let path = std::env::temp_dir().join(format!("kuma_{}_{}.chr", name, std::process::id()));
+        fs::write(&path, text).unwrap();
+
+        let res = CharData::open(&path);
+        fs::remove_file(&path).unwrap();
+        res
+    }
+
+    #[test]
+    fn open_good_file() {
+        let data = CharData::open(Path::new("./tests/kuma.chr")).unwrap();
+        let anims: Vec<(String, usize)> = data.anims().into_iter().map(|a| (a, 1)).collect();
+
+        assert_eq!(data.name, "KUMA");
+        assert!(data.state("idle").is_some());
+        assert_eq!(data.validate(&anims), Ok(()));
+    }
+
+    #[test]
+    fn open_unknown_key() {
+        let err = open_text("unknown", "state idle\nanim stop\nspeed 4\n").unwrap_err();
+        assert!(err.ends_with(":3: bad line `speed 4`"), "{}", err);
+
+        // before the first section
+        let err = open_text("no_section", "name KUMA\nanim stop\n").unwrap_err();
+        assert!(err.ends_with(":2: bad line `anim stop`"), "{}", err);
+    }
+
+    #[test]
+    fn open_negative_number() {
+        let err = open_text("negative", "move jab\nanim stop\ndamage -5\n").unwrap_err();
+        assert!(err.ends_with(":3: bad line `damage -5`"), "{}", err);
+
+        for line in ["invincible strike 0 -4", "armor 1 -2 -6"] {
+            let text = format!("move jab\nanim stop\n{}\n", line);
+            let err = open_text("negative_move", &text).unwrap_err();
+            assert!(
+                err.ends_with(&format!(":3: bad line `{}`", line)),
+                "{}",
+                err
+            );
+        }
+
+        for line in ["counter 6 -120", "punish -10 125"] {
+            let err = open_text("negative_bonus", line).unwrap_err();
+            assert!(
+                err.ends_with(&format!(":1: bad line `{}`", line)),
+                "{}",
+                err
+            );
+        }
+
+        for line in ["damage -5", "size 40 -10", "offset 0 -8"] {
+            let text = format!("projectile ball\nanim stop\n{}\n", line);
+            let err = open_text("negative_projectile", &text).unwrap_err();
+            assert!(
+                err.ends_with(&format!(":3: bad line `{}`", line)),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn validate_missing_anim() {
+        let data = CharData::open(Path::new("./tests/kuma.chr")).unwrap();
+        let idle = data.state("idle").unwrap().anim.clone();
+        let anims: Vec<(String, usize)> = data
+            .anims()
+            .into_iter()
+            .filter(|a| *a != idle)
+            .map(|a| (a, 1))
+            .collect();
+
+        let errors = data.validate(&anims).unwrap_err();
+        assert!(errors.contains(&format!("idle: no animation `{}`", idle)));
+    }
+}
@@ -0,0 +1,38 @@
+//! Frame events, from the cel user data of the .ase files.
+
+use crate::res::stage::parse_options;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Sound(String),      // ./tests/{name}.wav
+    Effect(String),     // window::effects::Kind, by name
+    Spawn(String),      // projectile of the character
+    Velocity(i32, i32), // px per tick: forward, up
+    Cancel,             // the move can be cancelled, even on a whiff
+}
+
+////////////////////////////////////////
+impl Event {
+    // "sound=swing effect=dust spawn=fireball velocity=3,10 cancel"
+    //
+    // unknown OR bad options are skipped
+    pub fn parse(text: &str) -> Vec<Event> {
+        parse_options(text)
+            .filter_map(|(key, value)| match (key, value) {
+                ("sound", name) if !name.is_empty() => Some(Event::Sound(name.to_string())),
+                ("effect", name) if !name.is_empty() => Some(Event::Effect(name.to_string())),
+                ("spawn", name) if !name.is_empty() => Some(Event::Spawn(name.to_string())),
+                ("velocity", xy) => {
+                    let (x, y) = xy.split_once(',').unwrap_or((xy, "0"));
+                    Some(Event::Velocity(x.parse().ok()?, y.parse().ok()?))
+                }
+                ("cancel", _) => Some(Event::Cancel),
+
+                _ => {
+                    log::warn!("Event: unknown `{}={}`", key, value);
+                    None
+                }
+            })
+            .collect()
+    }
+}
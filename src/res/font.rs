@@ -0,0 +1,422 @@
+//! Bitmap font and text rendering.
+
+use crate::{
+    res::sprite::argb_u32,
+    window::{
+        canvas::Canvas,
+        render::{Draw, Layer, RenderQueue},
+    },
+};
+use asefile::AsepriteFile;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const OUTLINE: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+];
+
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    pub data: Vec<u32>, // Vec<argb>
+    pub width: u32,
+    pub height: u32,
+    pub advance: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Font {
+    pub glyphs: HashMap<char, Glyph>,
+    pub line_height: u32,
+    pub space: u32,
+    pub uppercase: bool, // only has uppercase glyphs
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    pub color: Option<u32>, // None: glyph colours
+    pub outline: Option<u32>,
+    pub scale: u32,
+    pub align: Align,
+    pub wrap: Option<u32>, // max line width
+}
+
+////////////////////////////////////////
+impl Font {
+    // metrics:
+    //
+    // # comment
+    // image font.png        (OR font.ase)
+    // cell 8 8
+    // chars ABCDEFGHIJKLMNOPQRSTUVWXYZ
+    // chars 0123456789.,!?:-
+    // line 10
+    // space 4
+    // width I 3
+    pub fn open(path: &Path) -> Self {
+        let text = fs::read_to_string(path).unwrap();
+        let dir = path.parent().unwrap_or(Path::new("."));
+
+        let mut image = (vec![], 0_u32);
+        let mut cell = (8_u32, 8_u32);
+        let mut rows: Vec<Vec<char>> = vec![];
+        let mut widths: Vec<(char, u32)> = vec![];
+        let mut line_height = None;
+        let mut space = None;
+
+        for line in text.lines().map(str::trim) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let nums: Vec<u32> = value
+                .split_whitespace()
+                .filter_map(|n| n.parse().ok())
+                .collect();
+
+            match key {
+                "image" => image = load_image(&dir.join(value)),
+                "cell" if nums.len() == 2 => cell = (nums[0], nums[1]),
+                "chars" => rows.push(value.chars().filter(|c| *c != ' ').collect()),
+                "line" => line_height = nums.first().copied(),
+                "space" => space = nums.first().copied(),
+                "width" => {
+                    // the glyph may be more than one byte
+                    let mut chars = value.chars();
+
+                    if let (Some(c), Some(w)) = (chars.next(), chars.as_str().trim().parse().ok()) {
+                        widths.push((c, w));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (data, iw) = image;
+        let mut glyphs = HashMap::new();
+
+        for (row, chars) in rows.iter().enumerate() {
+            for (col, c) in chars.iter().enumerate() {
+                let mut glyph = cut(&data, iw, col as u32 * cell.0, row as u32 * cell.1, cell);
+
+                // trimmed width + 1
+                glyph.advance = glyph.used_width() + 1;
+                glyphs.insert(*c, glyph);
+            }
+        }
+
+        for (c, w) in widths {
+            if let Some(glyph) = glyphs.get_mut(&c) {
+                glyph.advance = w;
+            }
+        }
+
+        let uppercase = !glyphs.keys().any(|c| c.is_lowercase());
+
+        log::debug!("Font: {} glyphs", glyphs.len());
+
+        Self {
+            glyphs,
+            line_height: line_height.unwrap_or(cell.1 + 1),
+            space: space.unwrap_or(cell.0 / 2),
+            uppercase,
+        }
+    }
+
+    // 3x5 pixels
+    pub fn builtin() -> Self {
+        let chars = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-:.!/?+";
+        let mut glyphs = HashMap::new();
+
+        for c in chars.chars() {
+            let mut data = Vec::with_capacity(15);
+
+            for bits in builtin_glyph(c) {
+                for col in 0..3 {
+                    data.push(if bits & (0b100 >> col) != 0 {
+                        0xffffffff
+                    } else {
+                        0
+                    });
+                }
+            }
+
+            glyphs.insert(
+                c,
+                Glyph {
+                    data,
+                    width: 3,
+                    height: 5,
+                    advance: 4,
+                },
+            );
+        }
+
+        Self {
+            glyphs,
+            line_height: 7,
+            space: 4,
+            uppercase: true,
+        }
+    }
+
+    #[inline(always)]
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        if self.uppercase {
+            self.glyphs.get(&c.to_ascii_uppercase())
+        } else {
+            self.glyphs.get(&c)
+        }
+    }
+
+    fn advance(&self, c: char) -> u32 {
+        self.glyph(c).map_or(self.space, |g| g.advance)
+    }
+
+    // unscaled
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars().map(|c| self.advance(c)).sum()
+    }
+
+    // split into lines, wrap at spaces
+    pub fn layout(&self, text: &str, style: &TextStyle) -> Vec<String> {
+        let mut lines = vec![];
+        let max = style.wrap.map(|w| w / style.scale.max(1));
+
+        for para in text.split('\n') {
+            let Some(max) = max else {
+                lines.push(para.to_string());
+                continue;
+            };
+
+            let mut line = String::new();
+
+            for word in para.split(' ') {
+                let next = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+
+                if self.text_width(&next) > max && !line.is_empty() {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                } else {
+                    line = next;
+                }
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, x: i32, y: i32, text: &str, style: &TextStyle) {
+        let s = style.scale.max(1) as i32;
+
+        for (row, line) in self.layout(text, style).iter().enumerate() {
+            let w = (self.text_width(line) as i32) * s;
+            let mut gx = match style.align {
+                Align::Left => x,
+                Align::Center => x - w / 2,
+                Align::Right => x - w,
+            };
+            let gy = y + row as i32 * self.line_height as i32 * s;
+
+            for c in line.chars() {
+                if let Some(glyph) = self.glyph(c) {
+                    if let Some(outline) = style.outline {
+                        for (dx, dy) in OUTLINE {
+                            glyph.draw(canvas, gx + dx * s, gy + dy * s, s, Some(outline));
+                        }
+                    }
+
+                    glyph.draw(canvas, gx, gy, s, style.color);
+                }
+
+                gx += self.advance(c) as i32 * s;
+            }
+        }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        queue: &mut RenderQueue<'a>,
+        layer: Layer,
+        z: i32,
+        (x, y): (i32, i32),
+        text: String,
+        style: TextStyle,
+    ) {
+        queue.push(
+            layer,
+            z,
+            x,
+            y,
+            Draw::Custom(Box::new(move |canvas: &mut Canvas| {
+                self.draw(canvas, x, y, &text, &style);
+            })),
+        );
+    }
+}
+
+impl Glyph {
+    fn used_width(&self) -> u32 {
+        (0..self.width)
+            .rev()
+            .find(|x| (0..self.height).any(|y| self.data[(y * self.width + x) as usize] >> 24 != 0))
+            .map_or(0, |x| x + 1)
+    }
+
+    fn draw(&self, canvas: &mut Canvas, x: i32, y: i32, scale: i32, color: Option<u32>) {
+        for (idx, argb) in self.data.iter().enumerate() {
+            if *argb >> 24 == 0 {
+                continue;
+            }
+
+            let px = x + (idx as u32 % self.width) as i32 * scale;
+            let py = y + (idx as u32 / self.width) as i32 * scale;
+
+            canvas.fill_rect(px, py, scale as u32, scale as u32, color.unwrap_or(*argb));
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new(color: u32, scale: u32) -> Self {
+        Self {
+            color: Some(color),
+            outline: None,
+            scale,
+            align: Align::Left,
+            wrap: None,
+        }
+    }
+
+    pub fn outline(mut self, argb: u32) -> Self {
+        self.outline = Some(argb);
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn wrap(mut self, width: u32) -> Self {
+        self.wrap = Some(width);
+        self
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(0xffffffff, 1)
+    }
+}
+
+////////////////////////////////////////
+// *.png OR *.ase
+fn load_image(path: &Path) -> (Vec<u32>, u32) {
+    let mut data = vec![];
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ase") | Some("aseprite") => {
+            let ase = AsepriteFile::read_file(path).unwrap();
+            argb_u32(&mut data, ase.frame(0).image().as_raw());
+
+            (data, ase.width() as u32)
+        }
+
+        _ => {
+            let img = image::open(path).unwrap().to_rgba8();
+            argb_u32(&mut data, img.as_raw());
+
+            (data, img.width())
+        }
+    }
+}
+
+fn cut(data: &[u32], iw: u32, x: u32, y: u32, (width, height): (u32, u32)) -> Glyph {
+    let ih = (data.len() as u32).checked_div(iw).unwrap_or(0);
+    let mut res = Vec::with_capacity((width * height) as usize);
+
+    for row in y..y + height {
+        for col in x..x + width {
+            res.push(if row < ih && col < iw {
+                data[(row * iw + col) as usize]
+            } else {
+                0
+            });
+        }
+    }
+
+    Glyph {
+        data: res,
+        width,
+        height,
+        advance: width,
+    }
+}
+
+fn builtin_glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        _ => [0; 5],
+    }
+}
@@ -0,0 +1,357 @@
+//! Just enough JSON for the sheet exports of Aseprite and TexturePacker.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>), // in the order of the file
+}
+
+// missing keys and items
+static NULL: Json = Json::Null;
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+////////////////////////////////////////
+impl Json {
+    // Err: the byte offset and what was expected
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            at: 0,
+        };
+
+        let res = parser.value()?;
+        parser.space();
+
+        if parser.at < parser.text.len() {
+            return Err(parser.error("end of input"));
+        }
+
+        Ok(res)
+    }
+
+    // Null: not an object OR no such key
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Obj(list) => list
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&NULL, |(_, v)| v),
+            _ => &NULL,
+        }
+    }
+
+    // empty: not an array
+    pub fn items(&self) -> &[Json] {
+        match self {
+            Json::Arr(list) => list,
+            _ => &[],
+        }
+    }
+
+    // empty: not an object
+    pub fn entries(&self) -> &[(String, Json)] {
+        match self {
+            Json::Obj(list) => list,
+            _ => &[],
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Json::Num(n) => Some(*n as i32),
+            _ => None,
+        }
+    }
+
+    // negative: 0
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_i32().map(|n| n.max(0) as u32)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl Parser<'_> {
+    fn error(&self, expected: &str) -> String {
+        format!("at byte {}: expected {}", self.at, expected)
+    }
+
+    fn space(&mut self) {
+        while self.text.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    // skips the spaces before it
+    fn peek(&mut self) -> Option<u8> {
+        self.space();
+        self.text.get(self.at).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("`{}`", byte as char)));
+        }
+
+        self.at += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::Str(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+
+            _ => {
+                for (word, value) in [
+                    ("null", Json::Null),
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                ] {
+                    if self.text[self.at..].starts_with(word.as_bytes()) {
+                        self.at += word.len();
+                        return Ok(value);
+                    }
+                }
+
+                Err(self.error("a value"))
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        let mut list = vec![];
+        self.expect(b'{')?;
+
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Json::Obj(list));
+        }
+
+        loop {
+            self.space();
+            let key = self.string()?;
+            self.expect(b':')?;
+            list.push((key, self.value()?));
+
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => break,
+                _ => return Err(self.error("`,` OR `}`")),
+            }
+        }
+
+        self.at += 1;
+        Ok(Json::Obj(list))
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        let mut list = vec![];
+        self.expect(b'[')?;
+
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Json::Arr(list));
+        }
+
+        loop {
+            list.push(self.value()?);
+
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => break,
+                _ => return Err(self.error("`,` OR `]`")),
+            }
+        }
+
+        self.at += 1;
+        Ok(Json::Arr(list))
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let from = self.at;
+
+        while self
+            .text
+            .get(self.at)
+            .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.at += 1;
+        }
+
+        std::str::from_utf8(&self.text[from..self.at])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Json::Num)
+            .ok_or_else(|| self.error("a number"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.text.get(self.at) != Some(&b'"') {
+            return Err(self.error("a string"));
+        }
+        self.at += 1;
+
+        let mut res = vec![];
+
+        loop {
+            let Some(&byte) = self.text.get(self.at) else {
+                return Err(self.error("`\"`"));
+            };
+            self.at += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&esc) = self.text.get(self.at) else {
+                        return Err(self.error("an escape"));
+                    };
+                    self.at += 1;
+
+                    let c = match esc {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => self.unicode(),
+                        _ => esc as char,
+                    };
+
+                    res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+
+                _ => res.push(byte),
+            }
+        }
+
+        String::from_utf8(res).map_err(|_| self.error("UTF-8"))
+    }
+
+    // after `\u`, surrogate pairs are joined
+    fn unicode(&mut self) -> char {
+        let code = match self.hex() {
+            Some(high @ 0xd800..=0xdbff)
+                if self
+                    .text
+                    .get(self.at..)
+                    .is_some_and(|t| t.starts_with(b"\\u")) =>
+            {
+                self.at += 2;
+                self.hex()
+                    .map(|low| 0x10000 + ((high - 0xd800) << 10) + low.wrapping_sub(0xdc00))
+            }
+            code => code,
+        };
+
+        code.and_then(char::from_u32).unwrap_or('\u{fffd}')
+    }
+
+    // 4 digits
+    fn hex(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.at..self.at + 4)?;
+        self.at += 4;
+
+        u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        let json = Json::parse(r#""a\"b\\c\/d\n\t\r\b\f""#).unwrap();
+        assert_eq!(json.as_str(), Some("a\"b\\c/d\n\t\r\u{8}\u{c}"));
+    }
+
+    #[test]
+    fn unicode() {
+        let json = Json::parse(r#"["\u00e9", "\u65E5", "\ud83d\ude00", "\ud83d", "é😀"]"#).unwrap();
+        let items: Vec<_> = json.items().iter().map(|j| j.as_str().unwrap()).collect();
+
+        // a lone surrogate is replaced
+        assert_eq!(items, ["é", "日", "😀", "\u{fffd}", "é😀"]);
+    }
+
+    #[test]
+    fn numbers() {
+        let json = Json::parse("[0, -12, 3.5, 1e3, -2.5E-1, 7]").unwrap();
+        let items: Vec<_> = json.items().to_vec();
+
+        assert_eq!(
+            items,
+            [0.0, -12.0, 3.5, 1000.0, -0.25, 7.0]
+                .map(Json::Num)
+                .to_vec()
+        );
+        assert_eq!(items[1].as_i32(), Some(-12));
+        assert_eq!(items[1].as_u32(), Some(0));
+        assert_eq!(items[2].as_u32(), Some(3));
+    }
+
+    #[test]
+    fn nested() {
+        let text = r#"
+            {
+                "frames": [{"frame": {"x": 1, "y": 2}}, [], {}],
+                "meta": {"image": "a.png", "tags": [[true, false], null]}
+            }
+        "#;
+        let json = Json::parse(text).unwrap();
+
+        let frames = json.get("frames").items();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].get("frame").get("y").as_u32(), Some(2));
+        assert_eq!(frames[1], Json::Arr(vec![]));
+        assert_eq!(frames[2], Json::Obj(vec![]));
+
+        let meta = json.get("meta");
+        assert_eq!(meta.get("image").as_str(), Some("a.png"));
+        assert_eq!(
+            meta.get("tags").items()[0].items()[1].as_bool(),
+            Some(false)
+        );
+        assert!(meta.get("tags").items()[1].is_null());
+        assert!(meta.get("missing").get("deeper").is_null());
+
+        // in the order of the file
+        let keys: Vec<_> = json.entries().iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["frames", "meta"]);
+    }
+
+    #[test]
+    fn errors() {
+        for text in ["", "[1, 2", "{\"a\" 1}", "[1,]", "tru", "\"abc", "1 2", "-"] {
+            assert!(Json::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Palette swap for alternate character colours.
+
+use super::chara::CharData;
+use asefile::AsepriteFile;
+use palette::{FromColor, Hsv, Hue, Srgb};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// generated alternates when a character ships without palette files
+const ALT_HUES: [f32; 3] = [180.0, 90.0, 270.0];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: Vec<u32>, // Vec<argb>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ColorMap {
+    data: HashMap<u32, u32>, // rgb -> rgb
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorSet {
+    pub id: String,
+    pub list: Vec<Palette>, // list[0] is the original palette
+}
+
+////////////////////////////////////////
+impl Palette {
+    pub fn new(colors: Vec<u32>) -> Self {
+        Self { colors }
+    }
+
+    pub fn from_ase(ase: &AsepriteFile) -> Option<Self> {
+        let palette = ase.palette()?;
+        let mut colors = Vec::with_capacity(palette.num_colors() as usize);
+
+        for idx in 0..palette.num_colors() {
+            let [r, g, b, a] = palette.color(idx).map_or([0; 4], |c| c.raw_rgba8());
+
+            colors.push(u32::from_be_bytes([a, r, g, b]));
+        }
+
+        Some(Self::new(colors))
+    }
+
+    // *.gpl OR *.ase
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let err = |e: &dyn std::fmt::Display| format!("{}: {}", path.display(), e);
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gpl") => Ok(Self::from_gpl(
+                &fs::read_to_string(path).map_err(|e| err(&e))?,
+            )),
+            _ => Self::from_ase(&AsepriteFile::read_file(path).map_err(|e| err(&e))?)
+                .ok_or_else(|| err(&"no palette")),
+        }
+    }
+
+    // GIMP palette, exported by Aseprite
+    //
+    // GIMP Palette
+    // #
+    // 255 0 0 Red
+    pub fn from_gpl(text: &str) -> Self {
+        let mut colors = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("GIMP")
+                || line.starts_with("Name:")
+                || line.starts_with("Columns:")
+            {
+                continue;
+            }
+
+            let rgb: Vec<u8> = line
+                .split_whitespace()
+                .take(3)
+                .filter_map(|n| n.parse().ok())
+                .collect();
+
+            if let [r, g, b] = rgb[..] {
+                colors.push(u32::from_be_bytes([0xff, r, g, b]));
+            }
+        }
+
+        Self::new(colors)
+    }
+
+    pub fn hue_shift(&self, degrees: f32) -> Self {
+        let colors = self
+            .colors
+            .iter()
+            .map(|argb| {
+                let [a, r, g, b] = argb.to_be_bytes();
+                let hsv = Hsv::from_color(Srgb::new(r, g, b).into_format::<f32>());
+                let rgb = Srgb::from_color(hsv.shift_hue(degrees)).into_format::<u8>();
+
+                u32::from_be_bytes([a, rgb.red, rgb.green, rgb.blue])
+            })
+            .collect();
+
+        Self::new(colors)
+    }
+}
+
+impl ColorMap {
+    // same index, different colour; pixels are then matched by colour, not by index
+    pub fn new(from: &Palette, to: &Palette) -> Self {
+        let mut data = HashMap::new();
+
+        for (src, dst) in from.colors.iter().zip(to.colors.iter()) {
+            data.entry(src & 0xffffff).or_insert(dst & 0xffffff);
+        }
+
+        data.retain(|src, dst| src != dst);
+
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn apply(&self, frame: &mut [u32]) {
+        for argb in frame.iter_mut() {
+            // keep transparent pixels
+            if *argb >> 24 == 0 {
+                continue;
+            }
+
+            if let Some(rgb) = self.data.get(&(*argb & 0xffffff)) {
+                *argb = (*argb & 0xff000000) | rgb;
+            }
+        }
+    }
+}
+
+impl ColorSet {
+    // the original colours only
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            list: vec![Palette::default()],
+        }
+    }
+
+    // {dir}/{id}.chr, the original palette is the one of the idle animation's .ase
+    // {dir}/{id}_1.gpl, {dir}/{id}_2.gpl, ... OR .ase
+    //
+    // Err: no .chr, no idle state OR a sheet without .ase (PNG + JSON)
+    pub fn load(dir: &Path, id: &str) -> Result<Self, String> {
+        let data = CharData::open(&dir.join(format!("{}.chr", id)))?;
+        let idle = data
+            .state("idle")
+            .ok_or_else(|| format!("{}.chr: missing state `idle`", id))?;
+        let file = idle.anim.split(':').next().unwrap_or_default();
+
+        let base = Palette::open(&dir.join(format!("{}.ase", file)))?;
+        let mut list = vec![base.clone()];
+
+        for idx in 1.. {
+            let gpl = dir.join(format!("{}_{}.gpl", id, idx));
+            let ase = dir.join(format!("{}_{}.ase", id, idx));
+
+            if gpl.exists() {
+                list.push(Palette::open(&gpl)?);
+            } else if ase.exists() {
+                list.push(Palette::open(&ase)?);
+            } else {
+                break;
+            }
+        }
+
+        if list.len() == 1 {
+            for hue in ALT_HUES {
+                list.push(base.hue_shift(hue));
+            }
+        }
+
+        log::debug!("ColorSet: {} ({})", id, list.len());
+
+        Ok(Self {
+            id: id.to_string(),
+            list,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> &Palette {
+        &self.list[idx % self.list.len()]
+    }
+
+    // from the original palette to the colour `idx`, for any animation of the character
+    pub fn colors(&self, idx: usize) -> ColorMap {
+        ColorMap::new(&self.list[0], self.get(idx))
+    }
+}
+
+////////////////////////////////////////
+// character select
+//
+// p2 takes the next colour if both players picked the same character and colour
+pub fn pick_colors(p1: (&str, usize), p2: (&str, usize), len: usize) -> (usize, usize) {
+    let len = len.max(1);
+    let c1 = p1.1 % len;
+    let c2 = p2.1 % len;
+
+    if p1.0 == p2.0 && c1 == c2 {
+        (c1, (c2 + 1) % len)
+    } else {
+        (c1, c2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpl() {
+        let text = "GIMP Palette\nName: kuma\nColumns: 4\n#\n255   0   0\tRed\n  0 128 255 Blue\nbad line\n";
+
+        assert_eq!(
+            Palette::from_gpl(text),
+            Palette::new(vec![0xffff0000, 0xff0080ff])
+        );
+    }
+
+    #[test]
+    fn color_map() {
+        let from = Palette::new(vec![0xff000000, 0xffff0000, 0xff00ff00, 0xffff0000]);
+        let to = Palette::new(vec![0xff000000, 0xff0000ff, 0xffffff00, 0xff123456]);
+        let map = ColorMap::new(&from, &to);
+
+        // the alpha is kept, transparent pixels are not remapped
+        let mut frame = [0xffff0000, 0x80ff0000, 0x00ff0000, 0xff00ff00, 0xff000000];
+        map.apply(&mut frame);
+
+        assert_eq!(
+            frame,
+            [0xff0000ff, 0x800000ff, 0x00ff0000, 0xffffff00, 0xff000000]
+        );
+        assert!(ColorMap::new(&from, &from).is_empty());
+    }
+
+    #[test]
+    fn pick() {
+        assert_eq!(pick_colors(("kuma", 0), ("kuma", 0), 4), (0, 1));
+        assert_eq!(pick_colors(("kuma", 3), ("kuma", 7), 4), (3, 0));
+        assert_eq!(pick_colors(("kuma", 0), ("kuma", 2), 4), (0, 2));
+        assert_eq!(pick_colors(("kuma", 1), ("other", 1), 4), (1, 1));
+        assert_eq!(pick_colors(("kuma", 0), ("kuma", 0), 1), (0, 0));
+    }
+
+    #[test]
+    fn load() {
+        let set = ColorSet::load(Path::new("./tests"), "kuma").unwrap();
+        assert_eq!(set.len(), 1 + ALT_HUES.len());
+        assert!(set.colors(0).is_empty());
+
+        assert!(ColorSet::load(Path::new("./tests"), "none").is_err());
+
+        // PNG + JSON, no palette
+        let dir = std::env::temp_dir().join(format!("kuma_palette_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("json.chr"), "state idle\nanim idle\n").unwrap();
+
+        let err = ColorSet::load(&dir, "json").unwrap_err();
+        assert!(err.contains("idle.ase"), "{}", err);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
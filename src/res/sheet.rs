@@ -0,0 +1,415 @@
+//! One animation, from an .ase file OR a PNG sheet with its JSON export.
+
+use crate::{
+    res::{event::Event, json::Json, sprite::argb_u32, sprite::Block},
+    window::cube::Boxes,
+};
+use asefile::{AsepriteFile, Slice, SliceKey};
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub width: u32, // of the canvas
+    pub height: u32,
+    pub frames: Vec<Vec<u32>>,    // Vec<argb>, the whole canvas
+    pub boxes: Vec<Boxes<Block>>, // from slices: push*, hurt*, hit*, throw*
+    pub events: Vec<Vec<Event>>,  // from cel user data
+    pub durations: Vec<u32>,      // ms
+}
+
+////////////////////////////////////////
+impl Sheet {
+    // anim: file OR file:tag
+    //
+    // {dir}/{file}.ase, OR {dir}/{file}.json without one
+    //
+    // Err: the file and what is wrong with it
+    pub fn load(dir: &Path, anim: &str) -> Result<Self, String> {
+        let (id, tag) = match anim.split_once(':') {
+            Some((id, tag)) => (id, Some(tag)),
+            None => (anim, None),
+        };
+
+        let ase = dir.join(format!("{}.ase", id));
+        let json = dir.join(format!("{}.json", id));
+
+        let (path, res) = if ase.exists() {
+            (&ase, Self::from_ase(&ase, tag)?)
+        } else {
+            (&json, Self::from_json(&json, tag)?)
+        };
+
+        // an empty OR reversed tag, an empty sheet
+        if res.frames.is_empty() {
+            return Err(match tag {
+                Some(tag) => format!("{}: tag `{}` has no frames", path.display(), tag),
+                None => format!("{}: no frames", path.display()),
+            });
+        }
+
+        Ok(res)
+    }
+
+    pub fn from_ase(path: &Path, tag: Option<&str>) -> Result<Self, String> {
+        let ase =
+            AsepriteFile::read_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        log::debug!("Size: {}x{}", ase.width(), ase.height());
+        log::debug!("Frames: {}", ase.num_frames());
+        log::debug!("Layers: {}", ase.num_layers());
+        log::debug!("Tags: {}", ase.num_tags());
+
+        let (head, tail) = match tag {
+            Some(name) => {
+                let tag = ase
+                    .tag_by_name(name)
+                    .ok_or_else(|| format!("{}: no tag `{}`", path.display(), name))?;
+
+                (tag.from_frame(), tag.to_frame() + 1)
+            }
+            None => (0, ase.num_frames()),
+        };
+
+        let mut res = Self {
+            width: ase.width() as u32,
+            height: ase.height() as u32,
+            ..Self::default()
+        };
+
+        let mut tmp = vec![];
+
+        for idx in head..tail {
+            let mut bytes = ase.frame(idx).image().to_vec();
+            *bytes.last_mut().unwrap() = 0;
+
+            argb_u32(&mut tmp, &bytes);
+            res.frames.push(mem::take(&mut tmp));
+            res.boxes.push(frame_boxes(ase.slices(), idx));
+            res.events.push(frame_events(&ase, idx));
+            res.durations.push(ase.frame(idx).duration());
+        }
+
+        Ok(res)
+    }
+
+    // the JSON export of Aseprite OR TexturePacker, hash OR array of frames
+    //
+    // frames:  frame, rotated, spriteSourceSize, sourceSize, duration (ms, default 100),
+    //          in the order of the file
+    // meta:    image (next to the .json), frameTags, slices, layers[].cels[].data
+    pub fn from_json(path: &Path, tag: Option<&str>) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let json = Json::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let meta = json.get("meta");
+
+        let image = meta
+            .get("image")
+            .as_str()
+            .ok_or_else(|| format!("{}: no meta.image", path.display()))?;
+        let image = path.with_file_name(image);
+        let img = image::open(&image)
+            .map_err(|e| format!("{}: {}", image.display(), e))?
+            .to_rgba8();
+
+        let mut page = vec![];
+        argb_u32(&mut page, img.as_raw());
+
+        // array OR hash
+        let frames: Vec<&Json> = match json.get("frames") {
+            Json::Arr(list) => list.iter().collect(),
+            frames => frames.entries().iter().map(|(_, v)| v).collect(),
+        };
+
+        let (head, tail) = match tag {
+            Some(name) => {
+                let tag = meta
+                    .get("frameTags")
+                    .items()
+                    .iter()
+                    .find(|t| t.get("name").as_str() == Some(name))
+                    .ok_or_else(|| format!("{}: no tag `{}`", path.display(), name))?;
+
+                let from = tag.get("from").as_u32().unwrap_or(0);
+                let to = tag.get("to").as_u32().unwrap_or(from);
+                (from, to.saturating_add(1).min(frames.len() as u32))
+            }
+            None => (0, frames.len() as u32),
+        };
+
+        let slices = json_slices(meta);
+        let mut res = Self::default();
+
+        // the biggest canvas
+        for frame in frames.iter() {
+            let (w, h) = source_size(frame);
+            res.width = res.width.max(w);
+            res.height = res.height.max(h);
+        }
+
+        for idx in head..tail {
+            let frame = frames[idx as usize];
+            res.frames
+                .push(cut(frame, (&page, img.width()), (res.width, res.height)));
+            res.boxes.push(frame_boxes(&slices, idx));
+            res.events.push(json_events(meta, idx));
+            res.durations
+                .push(frame.get("duration").as_u32().unwrap_or(100));
+        }
+
+        log::debug!(
+            "Sheet: {} {}x{}, {} frames",
+            path.display(),
+            res.width,
+            res.height,
+            res.frames.len()
+        );
+
+        Ok(res)
+    }
+}
+
+////////////////////////////////////////
+// (w, h) of the untrimmed frame
+fn source_size(frame: &Json) -> (u32, u32) {
+    let size = frame.get("sourceSize");
+    let rect = frame.get("frame");
+
+    (
+        size.get("w")
+            .as_u32()
+            .or(rect.get("w").as_u32())
+            .unwrap_or(0),
+        size.get("h")
+            .as_u32()
+            .or(rect.get("h").as_u32())
+            .unwrap_or(0),
+    )
+}
+
+// the frame on a canvas of (width, height), back from a trimmed OR rotated cut
+fn cut(frame: &Json, (page, stride): (&[u32], u32), (width, height): (u32, u32)) -> Vec<u32> {
+    let rect = frame.get("frame");
+    let [x, y, w, h] = ["x", "y", "w", "h"].map(|k| rect.get(k).as_u32().unwrap_or(0));
+
+    // rotated: 90 degrees clockwise in the sheet, w and h are of the unrotated frame
+    let rotated = frame.get("rotated").as_bool().unwrap_or(false);
+
+    let src = frame.get("spriteSourceSize");
+    let (dx, dy) = (
+        src.get("x").as_u32().unwrap_or(0),
+        src.get("y").as_u32().unwrap_or(0),
+    );
+
+    let mut res = vec![0; (width * height) as usize];
+    let page_height = page.len() as u32 / stride.max(1);
+
+    for row in 0..h.min(height.saturating_sub(dy)) {
+        for col in 0..w.min(width.saturating_sub(dx)) {
+            let (sx, sy) = if rotated {
+                (x + h - 1 - row, y + col)
+            } else {
+                (x + col, y + row)
+            };
+
+            if sx < stride && sy < page_height {
+                res[((dy + row) * width + dx + col) as usize] = page[(sy * stride + sx) as usize];
+            }
+        }
+    }
+
+    res
+}
+
+// meta.slices: name, keys[].frame, keys[].bounds
+fn json_slices(meta: &Json) -> Vec<Slice> {
+    meta.get("slices")
+        .items()
+        .iter()
+        .map(|slice| Slice {
+            name: slice.get("name").as_str().unwrap_or_default().to_string(),
+            keys: slice
+                .get("keys")
+                .items()
+                .iter()
+                .map(|key| {
+                    let b = key.get("bounds");
+
+                    SliceKey {
+                        from_frame: key.get("frame").as_u32().unwrap_or(0),
+                        origin: (
+                            b.get("x").as_i32().unwrap_or(0),
+                            b.get("y").as_i32().unwrap_or(0),
+                        ),
+                        size: (
+                            b.get("w").as_u32().unwrap_or(0),
+                            b.get("h").as_u32().unwrap_or(0),
+                        ),
+                        slice9: None,
+                        pivot: None,
+                    }
+                })
+                .collect(),
+            user_data: None,
+        })
+        .collect()
+}
+
+// meta.layers[].cels[]: frame, data
+fn json_events(meta: &Json, frame: u32) -> Vec<Event> {
+    meta.get("layers")
+        .items()
+        .iter()
+        .flat_map(|layer| layer.get("cels").items())
+        .filter(|cel| cel.get("frame").as_u32() == Some(frame))
+        .filter_map(|cel| cel.get("data").as_str())
+        .flat_map(Event::parse)
+        .collect()
+}
+
+// cel user data of all the layers
+fn frame_events(ase: &AsepriteFile, frame: u32) -> Vec<Event> {
+    ase.layers()
+        .filter_map(|layer| layer.frame(frame).user_data()?.text.clone())
+        .flat_map(|text| Event::parse(&text))
+        .collect()
+}
+
+// the last key of each slice at `frame`, empty keys are skipped
+fn frame_boxes(slices: &[Slice], frame: u32) -> Boxes<Block> {
+    let mut res = Boxes::default();
+
+    for slice in slices.iter() {
+        let Some(key) = slice.keys.iter().rev().find(|k| k.from_frame <= frame) else {
+            continue;
+        };
+
+        if key.size.0 == 0 || key.size.1 == 0 {
+            continue;
+        }
+
+        let block = Block::new(
+            key.origin.0.max(0) as u32,
+            key.origin.1.max(0) as u32,
+            key.size.0,
+            key.size.1,
+        );
+        let name = slice.name.to_lowercase();
+
+        if name.starts_with("push") {
+            res.push = Some(block);
+        } else if name.starts_with("hurt") {
+            res.hurt.push(block);
+        } else if name.starts_with("hit") {
+            res.hit.push(block);
+        } else if name.starts_with("throw") {
+            res.throw.push(block);
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // the frame 1 2 3 / 4 5 6, trimmed from a 5x4 canvas at (1, 1)
+    const FRAME: [u32; 20] = [
+        0, 0, 0, 0, 0, //
+        0, 1, 2, 3, 0, //
+        0, 4, 5, 6, 0, //
+        0, 0, 0, 0, 0, //
+    ];
+
+    fn frame(x: u32, rotated: bool) -> Json {
+        let text = format!(
+            r#"{{
+                "frame": {{"x": {}, "y": 0, "w": 3, "h": 2}},
+                "rotated": {},
+                "spriteSourceSize": {{"x": 1, "y": 1, "w": 3, "h": 2}},
+                "sourceSize": {{"w": 5, "h": 4}}
+            }}"#,
+            x, rotated
+        );
+
+        Json::parse(&text).unwrap()
+    }
+
+    // {dir}/{name}.json and .png, frames of 1x1 with the tag `a`
+    pub(crate) fn write_sheet(dir: &Path, name: &str, frames: u32, (from, to): (u32, u32)) {
+        let png = format!("{}.png", name);
+        image::RgbaImage::from_pixel(2, 1, image::Rgba([255, 0, 0, 255]))
+            .save(dir.join(&png))
+            .unwrap();
+
+        let frames: Vec<String> = (0..frames)
+            .map(|idx| {
+                format!(
+                    r#"{{"frame": {{"x": {}, "y": 0, "w": 1, "h": 1}}}}"#,
+                    idx % 2
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{
+                "frames": [{}],
+                "meta": {{"image": "{}", "frameTags": [{{"name": "a", "from": {}, "to": {}}}]}}
+            }}"#,
+            frames.join(", "),
+            png,
+            from,
+            to
+        );
+
+        fs::write(dir.join(format!("{}.json", name)), json).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_no_frames() {
+        let dir = std::env::temp_dir().join(format!("kuma_sheet_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        write_sheet(&dir, "good", 2, (0, 1));
+        assert_eq!(Sheet::load(&dir, "good:a").unwrap().frames.len(), 2);
+
+        write_sheet(&dir, "reversed", 2, (1, 0));
+        write_sheet(&dir, "past_end", 2, (5, 6));
+
+        for anim in ["reversed:a", "past_end:a"] {
+            let err = Sheet::load(&dir, anim).unwrap_err();
+            assert!(err.ends_with("tag `a` has no frames"), "{}", err);
+        }
+
+        write_sheet(&dir, "empty", 0, (0, 0));
+        assert!(Sheet::load(&dir, "empty")
+            .unwrap_err()
+            .ends_with("no frames"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cut_trimmed() {
+        let page = [
+            9, 1, 2, 3, //
+            9, 4, 5, 6, //
+        ];
+        let frame = frame(1, false);
+
+        assert_eq!(source_size(&frame), (5, 4));
+        assert_eq!(cut(&frame, (&page, 4), (5, 4)), FRAME);
+    }
+
+    #[test]
+    fn cut_rotated() {
+        // 90 degrees clockwise
+        let page = [
+            9, 4, 1, 9, //
+            9, 5, 2, 9, //
+            9, 6, 3, 9, //
+        ];
+
+        assert_eq!(cut(&frame(1, true), (&page, 4), (5, 4)), FRAME);
+    }
+}
@@ -6,23 +6,53 @@
 //  cd
 //  hp
 
-use asefile;
+pub use crate::input::keymap::KeyMap;
+use crate::{
+    game::{
+        combat::Attack,
+        knockdown::{Bounce, Knockdown, Rise},
+        state::{self, Machine, State},
+    },
+    res::{
+        atlas::{Atlas, Region},
+        chara::{CharData, Input, Kind, Move, Projectile},
+        event::Event,
+        palette::ColorMap,
+        sheet::Sheet,
+    },
+    window::{
+        camera::Camera,
+        cube::{BoxAABB, Boxes},
+        render::{Draw, Layer, RenderQueue},
+    },
+    FPS,
+};
 use log;
 use minifb::{Key, Window};
 use std::mem;
 use std::path::Path;
 
+pub const HP_MAX: u32 = 1000;
+pub const EP_MAX: u32 = 300;
+pub const EP_START: u32 = 100;
+
+// presses are kept for motions, the last one may be buffered
+const INPUT_WINDOW: u32 = 30;
+const INPUT_BUFFER: u32 = 4;
+
 type Stream = Vec<Packet>;
-type Frame = Vec<u32>; // image buffer
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Packet {
-    tag: Movement,
-    right: Vec<Frame>, // Vec<argb>
-    left: Vec<Frame>,  // Vec<argb>
-    width: u32,
-    //blocks:PlayerBlock,
-    //checker: Dire,
+    name: String,        // file OR file:tag
+    frames: Vec<Region>, // in the atlas of the player, facing right
+    width: u32,          // of the canvas
+    height: u32,
+    boxes: Vec<Boxes<Block>>, // from slices: push*, hurt*, hit*, throw*
+    events: Vec<Vec<Event>>,  // from cel user data
+    ticks: Vec<u32>,          // per frame, from the durations of the art
+                              //blocks:PlayerBlock,
+                              //checker: Dire,
 }
 
 #[derive(Debug)]
@@ -51,6 +81,7 @@ pub struct Player {
     // v_wait:FramesInfo,
     // v_run:FramesInfo,
     pub stream: Stream,
+    pub atlas: Atlas,
     pub ptr_frame: usize,
     pub ptr_packet: usize,
 
@@ -64,17 +95,42 @@ pub struct Player {
 
     pub x_offset: u32,
     pub y_offset: u32,
-    pub movement: Movement,
+    pub stage_width: u32, // the walls: 0 and this, for the whole frame
+    pub vy: i32,          // px per tick, up
+    pub jump_dir: i32,    // 1: forward, -1: back
+    pub machine: Machine,
     pub status: Status,
 
-    pub speed: Speed,
     pub frame_timer: u8,
-    pub timer: u32,
 
     pub dire: Dire,
 
     pub is_p1: bool,
-    pub key_list: Vec<KeyCount>,
+    pub keys: Vec<KeyMap>, // held in this tick
+
+    pub attack: Attack,
+    pub hit_done: bool, // the current attack has connected
+    pub hitstop: u32,   // ticks left, frozen
+
+    // launched, knocked down OR moved by frame events
+    pub vx: i32,     // px per tick, forward
+    pub juggle: u32, // points of the hits in the air
+    pub bounce: Bounce,
+    pub down: Knockdown,
+    pub rise: Rise,
+    pub invincible: u32, // ticks left
+    pub armor: u32,      // hits left, of the current move
+    pub cancel: bool,    // opened by an event, until the move ends
+
+    pub events: Vec<Event>, // of the frame entered in this tick
+
+    pub chara: CharData,
+    pub chara_id: String,           // ./tests/{chara_id}.chr
+    pub move_id: Option<usize>,     // in chara.moves
+    pub presses: Vec<(Input, u32)>, // (input, age)
+
+    // alternate colours, applied in make_packet()
+    pub colors: ColorMap,
 }
 
 #[derive(Debug)]
@@ -83,14 +139,7 @@ pub struct Offset {
     y: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct KeyCount {
-    key: KeyMap,
-    count: u8,
-    timeout: u8,
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Block {
     x1: u32,
     x2: u32,
@@ -109,19 +158,16 @@ pub enum Speed {
     VerySlow = 24,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status {
     Null,
-}
-
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
-pub enum Movement {
-    #[default]
-    Stop = 0,
-
-    Walk = 1,
-
-    Run = 2,
+    Att(u32),   // frames since the start of the attack
+    Hit(u32),   // hitstun left
+    Guard(u32), // blockstun left
+    Down(u32),  // on the ground and waking up, ticks left
+    Throw,      // throwing, until the throw ends
+    Thrown,
+    Air, // launched, until the landing
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -134,204 +180,393 @@ pub enum Dire {
 }
 
 ////////////////////////////////////////
-impl KeyCount {
-    pub fn new(key: KeyMap, count: u8, timeout: u8) -> Self {
-        Self {
-            key,
-            count,
-            timeout,
-        }
-    }
-
-    pub fn null() -> Self {
-        Self::new(KeyMap::Unknown, 0, 0)
-    }
-
-    pub fn matched(&self, map: &KeyCount) -> bool {
-        self.key == map.key && self.count >= map.count
-    }
-}
-
 impl Player {
     pub fn new(dire: Dire, is_p1: bool) -> Self {
         Self {
-            timer: 0,
             ptr_frame: 0,
             ptr_packet: 0,
             stream: vec![],
-            hp: 1000,
-            ep: 100,
-            block_body: Block::new(0, 0, 100, 100),
-            block_head: Block::new(10, 10, 10, 10),
-            block_hand: Block::new(10, 10, 10, 10),
-            block_leg: Block::new(10, 10, 10, 10),
+            atlas: Atlas::new(),
+            hp: HP_MAX,
+            ep: EP_START,
+            // ./tests/stop.ase
+            block_body: Block::new(260, 340, 110, 110),
+            block_head: Block::new(285, 300, 60, 45),
+            block_hand: Block::new(360, 360, 120, 50),
+            block_leg: Block::new(255, 450, 120, 110),
 
             x_offset: 0,
             y_offset: 0,
-            movement: Movement::default(),
+            stage_width: u32::MAX,
+            vy: 0,
+            jump_dir: 0,
+            machine: Machine::new(),
             status: Status::Null,
 
-            speed: Speed::Norminal,
             frame_timer: Speed::Norminal as u8,
             dire,
             is_p1,
-            key_list: vec![KeyCount::null()],
+            keys: vec![],
+            attack: Attack::default(),
+            hit_done: false,
+            hitstop: 0,
+            vx: 0,
+            juggle: 0,
+            bounce: Bounce::None,
+            down: Knockdown::None,
+            rise: Rise::Normal,
+            invincible: 0,
+            armor: 0,
+            cancel: false,
+            events: vec![],
+            chara: CharData::default(),
+            chara_id: String::new(),
+            move_id: None,
+            presses: vec![],
+            colors: ColorMap::default(),
+        }
+    }
+
+    // start of a round
+    pub fn reset(&mut self, center: u32, dire: Dire) {
+        self.x_offset = center.saturating_sub(self.center() - self.x_offset);
+        self.y_offset = 0;
+        self.vy = 0;
+        self.dire = dire;
+        self.hp = HP_MAX;
+        self.ep = self.chara.meter.start;
+        self.status = Status::Null;
+        self.hitstop = 0;
+        self.vx = 0;
+        self.juggle = 0;
+        self.bounce = Bounce::None;
+        self.invincible = 0;
+        self.cancel = false;
+        self.events.clear();
+        self.presses.clear();
+        self.move_id = None;
+        self.switch_to(State::Idle);
+    }
+
+    // ./tests/{id}.chr and the animations it uses
+    pub fn load_chara(&mut self, id: &str) {
+        let data = CharData::open(Path::new(&format!("./tests/{}.chr", id)))
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        for anim in data.anims() {
+            if !self.stream.iter().any(|p| p.name == anim) {
+                self.load_stream(&anim);
+            }
+        }
+
+        let stats = self.atlas.stats();
+        log::info!(
+            "{}: {} frames, {} unique in {} pages, {} KiB (untrimmed: {} KiB)",
+            id,
+            stats.frames,
+            stats.unique,
+            stats.pages,
+            stats.packed / 1024,
+            stats.full / 1024
+        );
+
+        let anims: Vec<(String, usize)> = self
+            .stream
+            .iter()
+            .map(|p| (p.name.clone(), p.frames.len()))
+            .collect();
+
+        if let Err(errors) = data.validate(&anims) {
+            panic!("{}.chr:\n  {}", id, errors.join("\n  "));
         }
+
+        self.chara = data;
+        self.chara_id = id.to_string();
     }
 
-    pub fn load_stream(&mut self, id: &str) {
-        let ase =
-            asefile::AsepriteFile::read_file(Path::new(&format!("./tests/{}.ase", id))).unwrap();
-        log::debug!("Size: {}x{}", ase.width(), ase.height());
-        log::debug!("Frames: {}", ase.num_frames());
-        log::debug!("Layers: {}", ase.num_layers());
-        log::debug!("Tags: {}", ase.num_tags());
+    // anim: file OR file:tag, from ./tests/{file}.ase OR ./tests/{file}.json
+    pub fn load_stream(&mut self, anim: &str) {
+        let sheet = Sheet::load(Path::new("./tests"), anim).unwrap_or_else(|e| panic!("{}", e));
+        let packet = self.make_packet(anim, sheet);
 
+        self.stream.push(packet);
+    }
+
+    fn make_packet(&mut self, anim: &str, mut sheet: Sheet) -> Packet {
         let mut packet = Packet {
-            tag: Movement::from(id),
-            right: vec![],
-            left: vec![],
-            width: ase.width() as u32,
+            name: anim.to_string(),
+            frames: vec![],
+            width: sheet.width,
+            height: sheet.height,
+            boxes: mem::take(&mut sheet.boxes),
+            events: mem::take(&mut sheet.events),
+            ticks: sheet.durations.iter().map(|ms| (ms / FPS).max(1)).collect(),
         };
 
-        let head = 0;
-        let tail = ase.num_frames();
+        for frame in sheet.frames.iter_mut() {
+            self.colors.apply(frame);
 
-        let mut frames = Vec::with_capacity(tail as usize);
-        //let mut pts_list = Vec::with_capacity(tail as usize);
+            // mirrored when drawn
+            packet.frames.push(self.atlas.add(frame, packet.width));
+        }
+
+        packet
+    }
+
+    // hot reload of {dir}/{file}.ase, .json, .png OR {chara_id}.chr, the state is kept
+    //
+    // false: not used by this player, OR broken and the old data is kept
+    pub fn reload(&mut self, path: &Path) -> bool {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            return false;
+        };
 
-        for idx in head..tail {
-            //pts_list.push(FPS as u32 + frame.duration());
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("chr") if stem == self.chara_id => self.reload_chara(path),
+            // the .png of a sheet is named like its .json
+            Some("ase" | "json" | "png") => {
+                self.reload_anims(path.parent().unwrap_or(Path::new(".")), stem)
+            }
 
-            frames.push(mem::take(&mut ase.frame(idx).image().to_vec()));
-            *frames.last_mut().unwrap().last_mut().unwrap() = 0;
+            _ => false,
         }
+    }
 
-        //dbg!(&frames[0][0..40]);
+    // the old frames stay in the atlas until the next start
+    fn reload_anims(&mut self, dir: &Path, file: &str) -> bool {
+        let mut done = false;
 
-        let mut tmp: Vec<u32> = Vec::new();
+        for idx in 0..self.stream.len() {
+            let anim = self.stream[idx].name.clone();
 
-        for idx in 0..frames.len() {
-            argb_u32(&mut tmp, &mem::take(&mut frames[idx]));
+            if anim.split(':').next() != Some(file) {
+                continue;
+            }
+
+            let sheet = match Sheet::load(dir, &anim) {
+                Ok(sheet) => sheet,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    continue;
+                }
+            };
 
-            packet.right.push(tmp.clone());
+            self.stream[idx] = self.make_packet(&anim, sheet);
+            done = true;
 
-            turn(&mut tmp, ase.width(), ase.height());
-            packet.left.push(tmp.clone());
+            // a shorter animation
+            if idx == self.ptr_packet {
+                self.ptr_frame = self.ptr_frame.min(self.anim_len(idx).saturating_sub(1));
+            }
+
+            log::info!("Reloaded: {} ({} frames)", anim, self.anim_len(idx));
         }
 
-        self.stream.push(packet);
+        done
     }
 
-    #[inline(always)]
-    pub fn get_frame(&self) -> &[u32] {
-        let ptr = self.movement as usize;
+    fn reload_chara(&mut self, path: &Path) -> bool {
+        let data = match CharData::open(path) {
+            Ok(data) => data,
+            Err(e) => {
+                log::warn!("{}", e);
+                return false;
+            }
+        };
 
-        if self.dire == Dire::Left {
-            &self.stream[ptr].left[self.ptr_frame]
-        } else {
-            &self.stream[ptr].right[self.ptr_frame]
+        // new animations, added once the data is valid
+        let mut packets = vec![];
+
+        for anim in data.anims() {
+            if self.find_anim(&anim).is_some() {
+                continue;
+            }
+
+            match Sheet::load(Path::new("./tests"), &anim) {
+                Ok(sheet) => packets.push(self.make_packet(&anim, sheet)),
+                Err(e) => {
+                    log::warn!("{}", e);
+                    return false;
+                }
+            }
         }
-    }
 
-    #[inline(always)]
-    pub fn next_frame(&mut self) {
-        if self.frame_timer > 0 {
-            self.frame_timer -= 1;
-        } else {
-            let ptr = self.movement as usize;
+        let anims: Vec<(String, usize)> = (self.stream.iter().chain(packets.iter()))
+            .map(|p| (p.name.clone(), p.frames.len()))
+            .collect();
 
-            if self.ptr_frame + 1 < self.stream[ptr].right.len() {
-                self.ptr_frame += 1;
-            } else {
-                self.ptr_frame = 0;
+        if let Err(errors) = data.validate(&anims) {
+            log::warn!("{}:\n  {}", path.display(), errors.join("\n  "));
+            return false;
+        }
+
+        // appended, the indices of the others do not change
+        self.stream.append(&mut packets);
+
+        // the current move, by name
+        let name = self.move_id.map(|id| self.chara.moves[id].name.clone());
+        self.chara = data;
+        self.move_id = name.and_then(|name| self.chara.moves.iter().position(|mv| mv.name == name));
+
+        match self.move_id {
+            Some(id) => self.attack = self.chara.moves[id].attack.unwrap_or_default(),
+            None if self.machine.is(State::Attack) => {
+                self.status = Status::Null;
+                self.switch_to(State::Idle);
             }
-            self.frame_timer = self.speed as u8;
+            None => (),
         }
+
+        log::info!("Reloaded: {}", path.display());
+
+        true
     }
 
+    // the move while attacking, the state otherwise
     #[inline(always)]
-    pub fn switch_to(&mut self, movement: Movement) {
-        self.movement = movement;
-        self.ptr_frame = 0;
+    pub fn current(&self) -> &Move {
+        match self.move_id {
+            Some(id) if self.machine.is(State::Attack) => &self.chara.moves[id],
+            _ => self.chara.state(self.machine.state.name()).unwrap(),
+        }
     }
 
-    pub fn try_move(&mut self, _block_checker: &Dire) {
-        {
-            // doing nothing
-        }
+    // packets are found by name, the order of loading does not matter
+    fn find_packet(&self) -> usize {
+        let anim = &self.current().anim;
+
+        self.stream
+            .iter()
+            .position(|p| p.name == *anim)
+            .unwrap_or(0)
     }
 
+    // for other objects using the sprites of this player
     #[inline(always)]
-    pub fn move_walk(&mut self) {
-        if self.dire == Dire::Right {
-            // TODO:
-            if self.x_offset + 4 >= 200 * 2 {
-                // background OR stop
-                //self.x_offset = 0;
-            } else {
-                // player
-                self.x_offset += 4;
-            }
-        } else if self.dire == Dire::Left {
-            // TODO:
-            if self.x_offset >= 4 {
-                self.x_offset -= 4;
-            } else {
-            }
-        } else {
-        }
+    pub fn find_anim(&self, name: &str) -> Option<usize> {
+        self.stream.iter().position(|p| p.name == name)
     }
 
     #[inline(always)]
-    pub fn move_run(&mut self) {
-        if self.dire == Dire::Right {
-            // TODO:
-            if self.x_offset + 4 * 4 >= 200 * 2 {
-                // background OR stop
-                //self.x_offset = 0;
-            } else {
-                // player
-                self.x_offset += 4 * 4;
-            }
-        } else if self.dire == Dire::Left {
-            // TODO:
-            if self.x_offset >= 4 * 4 {
-                self.x_offset -= 4 * 4;
-            } else {
-            }
-        }
+    pub fn anim_len(&self, packet: usize) -> usize {
+        self.stream[packet].frames.len()
     }
-    pub fn move_up(&mut self) {}
-    pub fn move_down(&mut self) {}
 
-    // reset timer
+    // (draw, top left of it in the canvas, canvas size)
     #[inline(always)]
-    pub fn timeout(&self) -> bool {
-        if self.timer >= 24 {
-            true
+    pub fn anim_frame(
+        &self,
+        packet: usize,
+        frame: usize,
+        dire: Dire,
+    ) -> (Draw<'_>, (i32, i32), (u32, u32)) {
+        let packet = &self.stream[packet];
+        let region = &packet.frames[frame];
+        let flip = dire == Dire::Left;
+
+        (
+            self.atlas.draw(region, flip),
+            region.origin(packet.width, flip),
+            (packet.width, packet.height),
+        )
+    }
+
+    #[inline(always)]
+    pub fn next_frame(&mut self) {
+        let ptr = self.find_packet();
+        let len = self.stream[ptr].frames.len();
+        let reverse = self.current().reverse;
+
+        self.events.clear();
+
+        // new animation
+        if ptr != self.ptr_packet {
+            self.ptr_packet = ptr;
+            self.ptr_frame = if reverse { len - 1 } else { 0 };
+            self.frame_timer = self.frame_ticks();
+        } else if self.frame_timer > 0 {
+            self.frame_timer -= 1;
+            return;
         } else {
-            false
+            self.ptr_frame = if reverse {
+                self.ptr_frame.checked_sub(1).unwrap_or(len - 1)
+            } else if self.ptr_frame + 1 < len {
+                self.ptr_frame + 1
+            } else {
+                0
+            };
+            self.frame_timer = self.frame_ticks();
         }
+
+        self.fire_events();
     }
 
-    #[inline(always)]
-    pub fn keys_from(&mut self, keys: &[KeyMap]) {
-        for key in keys.iter() {
-            let last = self.key_list.last_mut().unwrap();
-
-            if last.key == *key {
-                if self.movement == Movement::Walk {
-                    last.count = u8::MIN;
-                } else if last.count < u8::MAX {
-                    last.count += 1;
+    // of the current frame: from the .chr, OR the duration in the art, up to u8::MAX
+    fn frame_ticks(&self) -> u8 {
+        let ticks = self.current().ticks.unwrap_or_else(|| {
+            let packet = &self.stream[self.ptr_packet];
+            packet.ticks.get(self.ptr_frame).copied().unwrap_or(1)
+        });
+
+        ticks.min(u8::MAX as u32) as u8
+    }
+
+    // entering a frame, the events of the player itself are applied here
+    fn fire_events(&mut self) {
+        let packet = &self.stream[self.ptr_packet];
+        self.events = packet
+            .events
+            .get(self.ptr_frame)
+            .cloned()
+            .unwrap_or_default();
+
+        for event in self.events.iter() {
+            match *event {
+                Event::Velocity(x, y) => {
+                    self.vx = x;
+                    if y != 0 {
+                        self.vy = y;
+                    }
                 }
-            } else {
-                self.key_list.push(KeyCount::new(*key, 0, 0));
+                Event::Cancel if self.is_attacking() => self.cancel = true,
+
+                _ => {}
             }
         }
     }
 
+    #[inline(always)]
+    pub fn switch_to(&mut self, next: State) {
+        state::switch(self, next);
+        self.ptr_frame = 0;
+    }
+
+    pub fn try_move(&mut self, _block_checker: &Dire) {
+        {
+            // doing nothing
+        }
+    }
+
+    // px, negative: backward
+    #[inline(always)]
+    pub fn move_forward(&mut self, dx: i32) {
+        let right = (self.dire == Dire::Right) == (dx >= 0);
+        let dx = dx.unsigned_abs();
+
+        self.x_offset = if right {
+            self.clamp_x(self.x_offset + dx)
+        } else {
+            self.x_offset.saturating_sub(dx)
+        };
+    }
+
+    // an x_offset kept inside the stage
+    pub fn clamp_x(&self, x: u32) -> u32 {
+        x.min(
+            self.stage_width
+                .saturating_sub(self.stream[self.ptr_packet].width),
+        )
+    }
     // FIXME:
     #[inline(always)]
     fn filter(&self, res: &mut Vec<KeyMap>, keys: &[Key]) {
@@ -362,191 +597,371 @@ impl Player {
 
     #[inline(always)]
     pub fn check_keys(&mut self, window: &Window) {
+        let keys = self.read_keys(window);
+
+        self.check_input(&keys);
+    }
+
+    // keyboard -> logical keys of this player
+    #[inline(always)]
+    pub fn read_keys(&self, window: &Window) -> Vec<KeyMap> {
         let keys = window.get_keys();
         let mut tmp: Vec<KeyMap> = Vec::with_capacity(keys.len());
-        let mut need_reset = false;
 
         self.check_keys_misc(&keys);
         self.filter(&mut tmp, &keys);
-        self.keys_from(&tmp);
-
-        self.inner_check_keys(window);
-
-        let last = self.key_list.last().unwrap();
-
-        // true:
-        //   Walk + A + A
-        //   Walk + D + D
-        // false:
-        //   Walk + A + D
-        //   Walk + D + A
-        need_reset = {
-            if (self.movement == Movement::Walk || self.movement == Movement::Run) {
-                match (last.key, self.dire) {
-                    (KeyMap::Left, Dire::Left) => false,
-                    (KeyMap::Right, Dire::Right) => false,
-                    _ => true,
-                }
-            } else {
-                true
+
+        tmp
+    }
+
+    #[inline(always)]
+    pub fn check_input(&mut self, tmp: &[KeyMap]) {
+        self.update_presses(tmp);
+        self.keys = tmp.to_vec();
+
+        if let Some(id) = self.find_move() {
+            self.start_move(id);
+        } else if self.presses.contains(&(Input::Up, 0))
+            && self.cancellable().is_some_and(|mv| mv.can_jump_cancel())
+            && self.y_offset == 0
+        {
+            self.status = Status::Null;
+            self.switch_to(State::Jump);
+        }
+
+        state::update(self);
+        self.move_to();
+    }
+
+    // held in this tick, relative to the facing
+    #[inline(always)]
+    pub fn holds(&self, input: Input) -> bool {
+        self.keys
+            .iter()
+            .any(|key| Input::from_key(*key, self.dire) == Some(input))
+    }
+
+    #[inline(always)]
+    fn check_keys_misc(&self, keys: &[Key]) {
+        match *keys {
+            // exit
+            [Key::Q] => {
+                std::process::exit(0);
             }
+
+            _ => {}
+        }
+    }
+
+    // on the ground and not attacking, toward the center of the opponent
+    pub fn face_toward(&mut self, x: u32) {
+        if self.y_offset > 0 || self.vy != 0 || self.is_attacking() {
+            return;
+        }
+
+        let center = self.center();
+        let dire = match x.cmp(&center) {
+            std::cmp::Ordering::Greater => Dire::Right,
+            std::cmp::Ordering::Less => Dire::Left,
+            std::cmp::Ordering::Equal => return,
         };
 
-        // if ( last == new )
-        // e.g.
-        //     Walk + D + (miss) + D = Run
-        //     Walk + D + D = Walk
-        let is_double = {
-            match (self.is_p1, self.dire) {
-                (true, Dire::Left) => window.is_key_down(minifb::Key::A),
-                (true, Dire::Right) => window.is_key_down(minifb::Key::D),
-                (false, Dire::Left) => window.is_key_down(minifb::Key::Left),
-                (false, Dire::Right) => window.is_key_down(minifb::Key::Right),
-
-                _ => false,
+        if dire == self.dire {
+            return;
+        }
+
+        self.dire = dire;
+
+        // forward and back of the buffered presses
+        for press in self.presses.iter_mut() {
+            press.0 = match press.0 {
+                Input::Forward => Input::Back,
+                Input::Back => Input::Forward,
+                input => input,
+            };
+        }
+    }
+
+    // keys pressed in this tick, before self.keys is updated
+    fn update_presses(&mut self, keys: &[KeyMap]) {
+        for press in self.presses.iter_mut() {
+            press.1 += 1;
+        }
+        self.presses.retain(|press| press.1 <= INPUT_WINDOW);
+
+        self.add_presses(keys);
+    }
+
+    // new keys only
+    fn add_presses(&mut self, keys: &[KeyMap]) {
+        for key in keys.iter().filter(|k| !self.keys.contains(k)) {
+            if let Some(input) = Input::from_key(*key, self.dire) {
+                self.presses.push((input, 0));
             }
-        };
+        }
+    }
 
-        let last = self.key_list.last_mut().unwrap();
+    // in hitstop: presses are kept for after it, without aging
+    pub fn hold(&mut self, keys: &[KeyMap]) {
+        self.hitstop -= 1;
+        self.add_presses(keys);
+        self.keys = keys.to_vec();
+    }
 
-        if is_double {
-        } else if last.key == KeyMap::Unknown && last.count < u8::MAX {
-            last.count += 1;
-        } else {
-            self.key_list.push(KeyCount::null());
+    // when free, OR cancelling the current move after a hit
+    fn find_move(&self) -> Option<usize> {
+        let cancel = self.cancellable();
+
+        if !self.is_actionable() && cancel.is_none() {
+            return None;
         }
 
-        self.move_to();
+        self.chara.moves.iter().position(|mv| {
+            cancel.is_none_or(|c| c.can_cancel(mv))
+                && mv.cost <= self.ep
+                && mv
+                    .command
+                    .matched(&self.presses, INPUT_BUFFER, |input| self.holds(input))
+        })
+    }
 
-        if self.timeout() && need_reset {
-            self.timer = 0;
-            self.key_list = vec![KeyCount::null()];
-            self.switch_to(Movement::Stop);
+    // the current move, after it connected and in its cancel window OR after an event
+    pub fn cancellable(&self) -> Option<&Move> {
+        match (self.move_id, self.status) {
+            (Some(id), Status::Att(_)) if self.cancel => Some(&self.chara.moves[id]),
+            (Some(id), Status::Att(frame)) if self.hit_done => {
+                let mv = &self.chara.moves[id];
+
+                mv.in_window(frame).then_some(mv)
+            }
+
+            _ => None,
         }
+    }
 
-        self.timer += 1;
+    pub fn start_move(&mut self, id: usize) {
+        self.move_id = Some(id);
+        self.attack = self.chara.moves[id].attack.unwrap_or_default();
+        self.ep -= self.chara.moves[id].cost;
+        self.armor = self.attack.armor.map_or(0, |a| a.hits);
+        self.presses.clear();
+        self.start_attack();
     }
 
     #[inline(always)]
-    fn inner_check_keys(&mut self, window: &Window) {
-        log::debug!("{:?}", &self.movement);
-        log::debug!("{:?}", self.key_list.as_slice());
-
-        // TODO:
-        match (self.movement, self.key_list.len(), self.key_list.as_slice()) {
-            // A+D+A+D+J
-            // BUG:
-            (_, len, keys) if (len >= 6) => {
-                if keys[1].matched(&KeyCount::new(KeyMap::Left, 0, 0))
-                    && keys[2].matched(&KeyCount::new(KeyMap::Right, 0, 0))
-                    && keys[3].matched(&KeyCount::new(KeyMap::Left, 0, 0))
-                    && keys[4].matched(&KeyCount::new(KeyMap::Right, 0, 0))
-                    && keys[5].matched(&KeyCount::new(KeyMap::Att, 0, 0))
-                {
-                    dbg!(self.movement);
-
-                    dbg!("A D A D J");
-                } else {
-                    return;
-                }
-            }
+    pub fn move_to(&mut self) {
+        state::apply_motion(self);
+    }
 
-            // stop -> Walk -> run
-            (Movement::Walk, len, keys) if (len >= 4) => {
-                if keys[1].matched(&KeyCount::new(KeyMap::Left, 0, 0))
-                    && keys[2].matched(&KeyCount::new(KeyMap::Unknown, 0, 0))
-                    && keys[3].matched(&KeyCount::new(KeyMap::Left, 0, 0))
-                {
-                    self.dire = Dire::Left;
-                    self.switch_to(Movement::Run);
-                } else if keys[1].matched(&KeyCount::new(KeyMap::Right, 0, 0))
-                    && keys[2].matched(&KeyCount::new(KeyMap::Unknown, 0, 0))
-                    && keys[3].matched(&KeyCount::new(KeyMap::Right, 0, 0))
-                {
-                    self.dire = Dire::Right;
-                    self.switch_to(Movement::Run);
-                }
-            }
+    #[inline(always)]
+    pub fn render<'a>(&'a self, queue: &mut RenderQueue<'a>, camera: &Camera, ground: u32) {
+        let (draw, (ox, oy), (bw, bh)) =
+            self.anim_frame(self.ptr_packet, self.ptr_frame, self.dire);
+
+        // stand on the ground
+        let x = self.x_offset as i32 - camera.x;
+        let y = ground as i32 - bh as i32 - self.y_offset as i32 - camera.y;
+
+        // the attacker is drawn on top
+        let z = if self.is_attacking() { 1 } else { 0 };
+
+        queue.push(Layer::Player, z, x + ox, y + oy, draw);
+
+        // shadow
+        queue.push(
+            Layer::Shadow,
+            0,
+            self.center() as i32 - camera.x,
+            ground as i32 - camera.y,
+            Draw::Ellipse {
+                rx: bw / 6,
+                ry: bw / 40,
+                argb: 0x60000000,
+            },
+        );
+    }
 
-            // stop -> walk
-            (Movement::Stop, len, keys) if (len >= 2) => {
-                if keys[0].matched(&KeyCount::new(KeyMap::Unknown, 0, 0)) {
-                } else {
-                    return;
-                }
+    // sprite coordinates, not mirrored
+    pub fn frame_boxes(&self) -> Boxes<Block> {
+        let boxes = &self.stream[self.ptr_packet].boxes;
+
+        match boxes.get(self.ptr_frame) {
+            Some(boxes) if !boxes.is_empty() => boxes.clone(),
 
-                if keys[1].matched(&KeyCount::new(KeyMap::Left, 0, 0)) {
-                    self.dire = Dire::Left;
-                    self.switch_to(Movement::Walk);
-                } else if keys[1].matched(&KeyCount::new(KeyMap::Right, 0, 0)) {
-                    self.dire = Dire::Right;
-                    self.switch_to(Movement::Walk);
+            _ => Boxes {
+                push: Some(self.block_body),
+                hurt: vec![self.block_head, self.block_body, self.block_leg],
+                hit: if self.is_active() && self.attack.throw == 0 {
+                    vec![self.block_hand]
                 } else {
-                    return;
-                }
-            }
+                    vec![]
+                },
+                // in front of the body
+                throw: if self.is_active() && self.attack.throw > 0 {
+                    let b = self.block_body;
+                    vec![Block::new(b.x2, b.y1, self.attack.throw, b.y2 - b.y1)]
+                } else {
+                    vec![]
+                },
+            },
+        }
+    }
 
-            _ => {}
+    // stage coordinates, y is the height above the ground
+    pub fn hitboxes(&self) -> Boxes<BoxAABB> {
+        let boxes = self.frame_boxes();
+
+        Boxes {
+            push: boxes.push.map(|b| self.aabb(&b)),
+            hurt: boxes.hurt.iter().map(|b| self.aabb(b)).collect(),
+            hit: boxes.hit.iter().map(|b| self.aabb(b)).collect(),
+            throw: boxes.throw.iter().map(|b| self.aabb(b)).collect(),
         }
     }
 
     #[inline(always)]
-    fn check_keys_misc(&mut self, keys: &[Key]) {
-        match *keys {
-            // exit
-            [Key::Q] => {
-                std::process::exit(0);
-            }
+    fn aabb(&self, block: &Block) -> BoxAABB {
+        let packet = &self.stream[self.ptr_packet];
+        let (bw, bh) = (packet.width, packet.height);
 
-            _ => {}
+        let (x1, x2) = if self.dire == Dire::Left {
+            (bw.saturating_sub(block.x2), bw.saturating_sub(block.x1))
+        } else {
+            (block.x1, block.x2)
+        };
+
+        BoxAABB::new(
+            self.x_offset + x1,
+            self.x_offset + x2,
+            self.y_offset + bh.saturating_sub(block.y2),
+            self.y_offset + bh.saturating_sub(block.y1),
+        )
+    }
+
+    #[inline(always)]
+    pub fn is_attacking(&self) -> bool {
+        matches!(self.status, Status::Att(_))
+    }
+
+    // the hitbox is out
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        match self.status {
+            Status::Att(frame) => self.attack.is_active(frame),
+            _ => false,
         }
     }
 
+    // in hitstun, in the air OR knocked down
     #[inline(always)]
-    pub fn move_to(&mut self) {
-        if self.movement == Movement::Run {
-            //dbg!(self.movement);
+    pub fn is_stunned(&self) -> bool {
+        matches!(self.status, Status::Hit(_) | Status::Air | Status::Down(_))
+    }
+
+    // free to move or attack
+    #[inline(always)]
+    pub fn is_actionable(&self) -> bool {
+        self.status == Status::Null
+    }
+
+    // projectile of the current move, on its frame
+    pub fn spawning(&self) -> Option<&Projectile> {
+        let (Some(id), Status::Att(frame)) = (self.move_id, self.status) else {
+            return None;
+        };
+
+        match &self.chara.moves[id].spawn {
+            Some((name, at)) if *at == frame => self.chara.projectile(name),
+            _ => None,
         }
+    }
 
-        match self.movement {
-            Movement::Walk => {
-                self.move_walk();
-            }
+    // sounds of the current move, on their frame
+    pub fn sounding(&self) -> impl Iterator<Item = &str> {
+        let (id, frame) = match (self.move_id, self.status) {
+            (Some(id), Status::Att(frame)) => (Some(id), frame),
+            _ => (None, 0),
+        };
+
+        id.into_iter()
+            .flat_map(|id| self.chara.moves[id].sounds.iter())
+            .filter(move |(_, at)| *at == frame)
+            .map(|(name, _)| name.as_str())
+    }
+
+    // holding back, not attacking or stunned
+    #[inline(always)]
+    pub fn is_guarding(&self, attacker: &Player) -> bool {
+        self.is_guarding_from(attacker.center())
+    }
+
+    // x: of the attack, stage coordinates
+    pub fn is_guarding_from(&self, x: u32) -> bool {
+        let back = if x > self.center() {
+            KeyMap::Left
+        } else {
+            KeyMap::Right
+        };
 
-            Movement::Run => {
-                self.move_run();
+        matches!(self.status, Status::Null | Status::Guard(_)) && self.keys.contains(&back)
+    }
+
+    pub fn start_attack(&mut self) {
+        self.status = Status::Att(0);
+        self.hit_done = false;
+        self.cancel = false;
+    }
+
+    // attack frames, hitstun and blockstun
+    #[inline(always)]
+    pub fn update_status(&mut self) {
+        if let Status::Att(frame) = self.status {
+            if frame + 1 >= self.attack.total() && !self.hit_done {
+                self.gain_ep(self.chara.meter.whiff);
             }
+        }
 
-            _ => {}
+        self.status = match self.status {
+            Status::Att(frame) if frame + 1 < self.attack.total() => Status::Att(frame + 1),
+            Status::Hit(left) if left > 1 => Status::Hit(left - 1),
+            Status::Guard(left) if left > 1 => Status::Guard(left - 1),
+            Status::Down(left) if left > 1 => Status::Down(left - 1),
+            Status::Throw | Status::Thrown | Status::Air => self.status,
+
+            _ => Status::Null,
+        };
+
+        // the velocity and the cancel of frame events last until the move ends
+        if self.status == Status::Null {
+            self.vx = 0;
+            self.cancel = false;
         }
     }
 
+    // up to the max of the character
     #[inline(always)]
-    pub fn flush_buffer(&self, buffer: &mut Vec<u32>, y: u32, width: u32) {
-        let color = self.get_frame();
-        let mut pos = 0_usize;
-        let mut idx: usize = (y as usize * width as usize) + self.x_offset as usize;
-
-        let bw = self.stream[self.movement as usize].width;
-        let bh = color.len() / bw as usize;
-        let _height: usize = buffer.len() / width as usize;
-
-        for _h in 0..bh {
-            for _offset in 0..bw as usize {
-                // (123,123,123,123)
-                if color[pos] == 8092539 {
-                } else {
-                    buffer[idx] = color[pos]
-                };
+    pub fn gain_ep(&mut self, n: u32) {
+        self.ep = (self.ep + n).min(self.chara.meter.max);
+    }
 
-                idx += 1;
-                pos += 1;
-            }
+    // full bars
+    #[inline(always)]
+    pub fn ep_level(&self) -> u32 {
+        self.ep / self.chara.meter.level_size().max(1)
+    }
 
-            //idx += width as usize - bw as usize - 1;
-            idx += width as usize - bw as usize;
+    // the first frame of a super, the world stops for the flash
+    pub fn is_super_start(&self) -> bool {
+        match (self.move_id, self.status) {
+            (Some(id), Status::Att(0)) => self.chara.moves[id].kind == Kind::Super,
+            _ => false,
         }
     }
+
+    #[inline(always)]
+    pub fn center(&self) -> u32 {
+        self.x_offset + self.stream[self.ptr_packet].width / 2
+    }
 }
 
 // TODO: remove
@@ -630,32 +1045,6 @@ impl From<&Speed> for u8 {
     }
 }
 
-impl From<&str> for Movement {
-    fn from(value: &str) -> Self {
-        match value {
-            "stop" => Movement::Stop,
-            "walk" => Movement::Walk,
-            "run" => Movement::Run,
-            _ => {
-                todo!()
-            }
-        }
-    }
-}
-
-impl From<Movement> for usize {
-    fn from(value: Movement) -> Self {
-        match value {
-            Movement::Stop => 0,
-            Movement::Walk => 1,
-            Movement::Run => 2,
-            _ => {
-                todo!()
-            }
-        }
-    }
-}
-
 ////////////////////////////////////////
 #[inline(always)]
 fn rgba_as_argb_u32(r: &u8, g: &u8, b: &u8, a: &u8) -> u32 {
@@ -675,26 +1064,6 @@ fn rgba_as_u32(arr: &[u8; 4]) -> u32 {
     r + g + b + a
 }
 
-#[inline]
-fn turn(img: &mut Vec<u32>, iw: usize, ih: usize) {
-    let mut left: usize = 0;
-    let mut right: usize = 0;
-    let split = iw / 2;
-
-    // TODO:
-    for y in 1..=ih {
-        right = iw * y - 1;
-        left = iw * y - iw;
-
-        for _x in 0..split {
-            img.swap(left, right);
-
-            left += 1;
-            right -= 1;
-        }
-    }
-}
-
 #[inline(always)]
 pub fn argb_u32(buffer: &mut Vec<u32>, bytes: &[u8]) {
     *buffer = vec![0; bytes.len() / 4];
@@ -704,75 +1073,48 @@ pub fn argb_u32(buffer: &mut Vec<u32>, bytes: &[u8]) {
     }
 }
 
-// TODO:
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum KeyMap {
-    Quit = -1,
-    Unknown = 0,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::sheet::tests::write_sheet;
+    use std::fs;
+
+    #[test]
+    fn clamp_to_stage() {
+        let mut p = Player::new(Dire::Right, true);
+        p.stream.push(Packet {
+            width: 100,
+            ..Packet::default()
+        });
+        p.stage_width = 500;
+
+        assert_eq!(p.clamp_x(250), 250);
+        assert_eq!(p.clamp_x(450), 400);
+
+        p.x_offset = 390;
+        p.move_forward(30);
+        assert_eq!(p.x_offset, 400);
+    }
 
-    Left = 1,
-    Right = 2,
+    #[test]
+    fn reload_keeps_old_anim_on_empty_tag() {
+        let dir = std::env::temp_dir().join(format!("kuma_reload_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
 
-    Up = 3,
-    Down = 4,
+        write_sheet(&dir, "anim", 2, (0, 1));
+        let mut p = Player::new(Dire::Right, true);
+        let packet = p.make_packet("anim:a", Sheet::load(&dir, "anim:a").unwrap());
+        p.stream.push(packet);
 
-    Att = 11,
-}
+        write_sheet(&dir, "anim", 3, (0, 2));
+        assert!(p.reload(&dir.join("anim.json")));
+        assert_eq!(p.anim_len(0), 3);
 
-impl KeyMap {
-    pub fn from_key_list(list: &[minifb::Key]) -> Vec<Self> {
-        let mut res = Vec::with_capacity(list.len());
+        // the tag is reversed while editing
+        write_sheet(&dir, "anim", 3, (2, 0));
+        assert!(!p.reload(&dir.join("anim.json")));
+        assert_eq!(p.anim_len(0), 3);
 
-        for key in list.iter() {
-            res.push(Self::from(key));
-        }
-
-        res
-    }
-}
-
-impl From<&minifb::Key> for KeyMap {
-    fn from(value: &minifb::Key) -> Self {
-        use minifb::Key;
-
-        match *value {
-            // p1
-            Key::W => Self::Up,
-            Key::S => Self::Down,
-            Key::A => Self::Left,
-            Key::D => Self::Right,
-            Key::J => Self::Att,
-            // p2
-            Key::Up => Self::Up,
-            Key::Down => Self::Down,
-            Key::Left => Self::Left,
-            Key::Right => Self::Right,
-            Key::NumPad1 => Self::Att,
-
-            _ => Self::Unknown,
-        }
-    }
-}
-
-impl Into<minifb::Key> for &KeyMap {
-    fn into(self) -> minifb::Key {
-        use minifb::Key;
-
-        match self {
-            // p1
-            KeyMap::Up => Key::W,
-            KeyMap::Down => Key::S,
-            KeyMap::Left => Key::A,
-            KeyMap::Right => Key::D,
-            KeyMap::Att => Key::J,
-            // p2
-            KeyMap::Up => Key::Up,
-            KeyMap::Down => Key::Down,
-            KeyMap::Left => Key::Left,
-            KeyMap::Right => Key::Right,
-            KeyMap::Att => Key::NumPad1,
-
-            _ => Key::Unknown,
-        }
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
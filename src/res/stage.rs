@@ -0,0 +1,202 @@
+//! Stage backgrounds with parallax layers.
+
+use crate::{
+    res::sprite::{argb_u32, Speed},
+    window::{
+        camera::Camera,
+        render::{Layer, RenderQueue},
+    },
+};
+use asefile::AsepriteFile;
+use std::path::Path;
+
+type Frame = Vec<u32>; // image buffer
+
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub width: u32,
+    pub height: u32,
+    pub ground: u32, // floor height of players
+    pub layers: Vec<StageLayer>,
+    pub music: Option<String>, // looping, by name
+}
+
+#[derive(Debug, Clone)]
+pub struct StageLayer {
+    pub frames: Vec<Frame>, // Vec<argb>
+    pub width: u32,
+
+    pub x: i32,
+    pub y: i32,
+    pub parallax: f32, // 0.0: fixed, 1.0: moves with the players
+    pub repeat: bool,  // tile horizontally
+
+    pub speed: u8,
+    pub ptr_frame: usize,
+    pub frame_timer: u8,
+}
+
+////////////////////////////////////////
+impl Stage {
+    pub fn new(width: u32, height: u32, ground: u32) -> Self {
+        Self {
+            width,
+            height,
+            ground,
+            layers: vec![],
+            music: None,
+        }
+    }
+
+    // single colour
+    pub fn flat(width: u32, height: u32, rgb: u32) -> Self {
+        let argb = 0xff000000 | rgb;
+        let layer = StageLayer::new(vec![vec![argb; (width * height) as usize]], width);
+
+        Self::new(width, height, height).layer(layer)
+    }
+
+    pub fn layer(mut self, layer: StageLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    // one stage layer per .ase layer, from the bottom to the top
+    //
+    // sprite user data: "ground=900 music=stage1"
+    // layer user data:  "parallax=0.5 repeat speed=6 x=0 y=0"
+    pub fn from_ase(path: &Path) -> Self {
+        let ase = AsepriteFile::read_file(path).unwrap();
+        let (width, height) = (ase.width() as u32, ase.height() as u32);
+        let mut stage = Self::new(width, height, height);
+
+        if let Some(text) = ase.sprite_user_data().and_then(|data| data.text.as_ref()) {
+            for (key, value) in parse_options(text) {
+                match key {
+                    "ground" => stage.ground = value.parse().unwrap_or(height),
+                    "music" => stage.music = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+
+        for layer in ase.layers().filter(|layer| layer.is_visible()) {
+            let mut frames: Vec<Frame> = Vec::with_capacity(ase.num_frames() as usize);
+            let mut tmp = Vec::new();
+
+            for idx in 0..ase.num_frames() {
+                argb_u32(&mut tmp, layer.frame(idx).image().as_raw());
+                frames.push(tmp.clone());
+            }
+
+            // static layer
+            if frames.windows(2).all(|f| f[0] == f[1]) {
+                frames.truncate(1);
+            }
+
+            let mut res = StageLayer::new(frames, width);
+
+            if let Some(text) = layer.user_data().and_then(|data| data.text.as_ref()) {
+                res.set_options(text);
+            }
+
+            log::debug!("StageLayer: {} {:?}", layer.name(), res.parallax);
+
+            stage.layers.push(res);
+        }
+
+        stage
+    }
+
+    pub fn next_frame(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.next_frame();
+        }
+    }
+
+    pub fn render<'a>(&'a self, queue: &mut RenderQueue<'a>, camera: &Camera) {
+        for (z, layer) in self.layers.iter().enumerate() {
+            layer.render(queue, camera, z as i32);
+        }
+    }
+}
+
+impl StageLayer {
+    pub fn new(frames: Vec<Frame>, width: u32) -> Self {
+        Self {
+            frames,
+            width,
+            x: 0,
+            y: 0,
+            parallax: 1.0,
+            repeat: false,
+            speed: Speed::Norminal as u8,
+            ptr_frame: 0,
+            frame_timer: Speed::Norminal as u8,
+        }
+    }
+
+    pub fn from_png(path: &Path, parallax: f32) -> Self {
+        let img = image::open(path).unwrap().to_rgba8();
+        let mut frame = Vec::new();
+
+        argb_u32(&mut frame, img.as_raw());
+
+        let mut res = Self::new(vec![frame], img.width());
+        res.parallax = parallax;
+
+        res
+    }
+
+    pub fn set_options(&mut self, text: &str) {
+        for (key, value) in parse_options(text) {
+            match key {
+                "parallax" => self.parallax = value.parse().unwrap_or(self.parallax),
+                "repeat" => self.repeat = true,
+                "speed" => self.speed = value.parse().unwrap_or(self.speed),
+                "x" => self.x = value.parse().unwrap_or(self.x),
+                "y" => self.y = value.parse().unwrap_or(self.y),
+                _ => {}
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn next_frame(&mut self) {
+        if self.frames.len() < 2 {
+            return;
+        }
+
+        if self.frame_timer > 0 {
+            self.frame_timer -= 1;
+        } else {
+            self.ptr_frame = (self.ptr_frame + 1) % self.frames.len();
+            self.frame_timer = self.speed;
+        }
+    }
+
+    pub fn render<'a>(&'a self, queue: &mut RenderQueue<'a>, camera: &Camera, z: i32) {
+        let frame = &self.frames[self.ptr_frame];
+        let x = self.x - (camera.x as f32 * self.parallax) as i32;
+        let y = self.y - (camera.y as f32 * self.parallax) as i32;
+
+        if self.repeat && self.width > 0 {
+            let width = self.width as i32;
+            let mut x = x.rem_euclid(width) - width;
+
+            while x < camera.width as i32 {
+                queue.sprite(Layer::Stage, z, x, y, frame, self.width);
+                x += width;
+            }
+        } else {
+            queue.sprite(Layer::Stage, z, x, y, frame, self.width);
+        }
+    }
+}
+
+////////////////////////////////////////
+// "key=value key"
+pub fn parse_options(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.split_whitespace()
+        .map(|opt| opt.split_once('=').unwrap_or((opt, "")))
+}
@@ -0,0 +1,77 @@
+//! Changed files in the asset directory, for hot reloading during development.
+
+use crate::FPS;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// ticks between two looks at the directory
+const POLL_TIME: u32 = 500 / FPS; // 0.5 s
+
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    dir: PathBuf,
+    times: HashMap<PathBuf, SystemTime>, // last modified
+    pending: Vec<PathBuf>,               // changed at the last poll, maybe still being written
+    timer: u32,
+}
+
+////////////////////////////////////////
+impl Watcher {
+    // the files there now are not reported
+    pub fn new(dir: &Path) -> Self {
+        let mut res = Self {
+            dir: dir.to_path_buf(),
+            times: HashMap::new(),
+            pending: vec![],
+            timer: POLL_TIME,
+        };
+
+        res.scan();
+        log::info!("Watcher: {} ({} files)", dir.display(), res.times.len());
+
+        res
+    }
+
+    // every tick, files are reported once they stop changing for a poll
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return vec![];
+        }
+
+        self.timer = POLL_TIME;
+
+        let changed = self.scan();
+        let res = self
+            .pending
+            .drain(..)
+            .filter(|path| !changed.contains(path))
+            .collect();
+
+        self.pending = changed;
+        res
+    }
+
+    // new OR modified since the last scan
+    fn scan(&mut self) -> Vec<PathBuf> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return vec![];
+        };
+
+        let mut res = vec![];
+
+        for path in dir.flatten().map(|e| e.path()) {
+            let Ok(time) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                continue;
+            };
+
+            if self.times.insert(path.clone(), time) != Some(time) {
+                res.push(path);
+            }
+        }
+
+        res
+    }
+}
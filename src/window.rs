@@ -1 +1,7 @@
+pub mod camera;
+pub mod canvas;
 pub mod cube;
+pub mod effects;
+pub mod hud;
+pub mod overlay;
+pub mod render;
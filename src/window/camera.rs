@@ -0,0 +1,59 @@
+//! Camera, in stage coordinates.
+
+// a fixed pattern, the same hit always shakes the same way
+const SHAKE: [(i32, i32); 4] = [(1, 1), (-1, -1), (1, -1), (-1, 1)];
+const SHAKE_TIME: u32 = 16; // ticks
+const SHAKE_MAX: u32 = 8; // px
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Camera {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+
+    // render only, the game never reads the camera
+    shake: u32, // ticks left
+    power: u32, // px
+}
+
+////////////////////////////////////////
+impl Camera {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            shake: 0,
+            power: 0,
+        }
+    }
+
+    // bigger hits shake more
+    pub fn shake(&mut self, damage: u32) {
+        self.power = self.power.max((damage / 20).clamp(1, SHAKE_MAX));
+        self.shake = SHAKE_TIME;
+    }
+
+    // keep both players in the middle of the screen
+    pub fn follow(&mut self, p1: u32, p2: u32, stage_width: u32) {
+        let mid = (p1 as i32 + p2 as i32) / 2;
+        let max = stage_width.saturating_sub(self.width) as i32;
+
+        self.x = (mid - self.width as i32 / 2).clamp(0, max);
+        self.y = 0;
+
+        if self.shake > 0 {
+            // fades out
+            let (dx, dy) = SHAKE[self.shake as usize % SHAKE.len()];
+            let power = (self.power * self.shake).div_ceil(SHAKE_TIME) as i32;
+
+            self.x += dx * power;
+            self.y += dy * power;
+            self.shake -= 1;
+        } else {
+            self.power = 0;
+        }
+    }
+}
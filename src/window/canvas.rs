@@ -0,0 +1,208 @@
+//! Framebuffer drawing with clipping.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Blend {
+    #[default]
+    Alpha,
+    Add, // brighter, for sparks and flashes
+}
+
+#[derive(Debug)]
+pub struct Canvas<'a> {
+    pub data: &'a mut [u32], // Vec<argb>
+    pub width: u32,
+    pub height: u32,
+}
+
+////////////////////////////////////////
+impl<'a> Canvas<'a> {
+    pub fn new(data: &'a mut [u32], width: u32) -> Self {
+        Self {
+            height: (data.len() / width as usize) as u32,
+            data,
+            width,
+        }
+    }
+
+    #[inline(always)]
+    pub fn clear(&mut self, argb: u32) {
+        self.data.fill(argb);
+    }
+
+    // copy `img` to (x, y), skip transparent pixels
+    pub fn blit(&mut self, x: i32, y: i32, img: &[u32], iw: u32) {
+        if iw == 0 {
+            return;
+        }
+
+        let ih = (img.len() / iw as usize) as i32;
+        let (w, h) = (self.width as i32, self.height as i32);
+
+        let (x0, x1) = (x.max(0), (x + iw as i32).min(w));
+        let (y0, y1) = (y.max(0), (y + ih).min(h));
+
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let len = (x1 - x0) as usize;
+
+        for row in y0..y1 {
+            let src = ((row - y) * iw as i32 + (x0 - x)) as usize;
+            let dst = (row * w + x0) as usize;
+
+            for (d, s) in self.data[dst..dst + len]
+                .iter_mut()
+                .zip(img[src..src + len].iter())
+            {
+                if *s >> 24 != 0 {
+                    *d = *s;
+                }
+            }
+        }
+    }
+
+    // like blit(), alpha: multiplied with the alpha of each pixel
+    pub fn blit_blend(&mut self, x: i32, y: i32, img: &[u32], iw: u32, mode: Blend, alpha: u32) {
+        if iw == 0 {
+            return;
+        }
+
+        let ih = (img.len() / iw as usize) as i32;
+        let (w, h) = (self.width as i32, self.height as i32);
+
+        let (x0, x1) = (x.max(0), (x + iw as i32).min(w));
+        let (y0, y1) = (y.max(0), (y + ih).min(h));
+
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let len = (x1 - x0) as usize;
+
+        for row in y0..y1 {
+            let src = ((row - y) * iw as i32 + (x0 - x)) as usize;
+            let dst = (row * w + x0) as usize;
+
+            for (d, s) in self.data[dst..dst + len]
+                .iter_mut()
+                .zip(img[src..src + len].iter())
+            {
+                let a = (*s >> 24) * alpha.min(0xff) / 0xff;
+                let s = (a << 24) | (*s & 0xffffff);
+
+                *d = match mode {
+                    Blend::Alpha => blend(*d, s),
+                    Blend::Add => add(*d, s),
+                };
+            }
+        }
+    }
+
+    // like blit(), from a part of `img`, mirrored if `flip`
+    pub fn blit_region(
+        &mut self,
+        x: i32,
+        y: i32,
+        img: &[u32],
+        stride: u32,
+        (sx, sy, iw, ih): (u32, u32, u32, u32),
+        flip: bool,
+    ) {
+        let (w, h) = (self.width as i32, self.height as i32);
+
+        let (x0, x1) = (x.max(0), (x + iw as i32).min(w));
+        let (y0, y1) = (y.max(0), (y + ih as i32).min(h));
+
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        for row in y0..y1 {
+            let src = ((sy + (row - y) as u32) * stride + sx) as usize;
+            let src = &img[src..src + iw as usize];
+            let dst = (row * w) as usize;
+
+            for col in x0..x1 {
+                let idx = (col - x) as usize;
+                let s = if flip {
+                    src[src.len() - 1 - idx]
+                } else {
+                    src[idx]
+                };
+
+                if s >> 24 != 0 {
+                    self.data[dst + col as usize] = s;
+                }
+            }
+        }
+    }
+
+    // alpha blending
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, argb: u32) {
+        let (w, h) = (self.width as i32, self.height as i32);
+
+        let (x0, x1) = (x.max(0), (x + width as i32).min(w));
+        let (y0, y1) = (y.max(0), (y + height as i32).min(h));
+
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        for row in y0..y1 {
+            let dst = (row * w) as usize;
+
+            for d in self.data[dst + x0 as usize..dst + x1 as usize].iter_mut() {
+                *d = blend(*d, argb);
+            }
+        }
+    }
+
+    pub fn fill_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, argb: u32) {
+        let (rx, ry) = (rx.max(1) as i32, ry.max(1) as i32);
+
+        for dy in -ry..=ry {
+            // x^2 / rx^2 + y^2 / ry^2 <= 1
+            let t = 1.0 - (dy * dy) as f32 / (ry * ry) as f32;
+            let dx = (rx as f32 * t.sqrt()) as i32;
+
+            self.fill_rect(cx - dx, cy + dy, (dx * 2 + 1) as u32, 1, argb);
+        }
+    }
+}
+
+////////////////////////////////////////
+#[inline(always)]
+pub fn blend(dst: u32, src: u32) -> u32 {
+    let a = src >> 24;
+
+    match a {
+        0 => dst,
+        0xff => src,
+        _ => {
+            let mix = |shift: u32| {
+                let s = (src >> shift) & 0xff;
+                let d = (dst >> shift) & 0xff;
+
+                ((s * a + d * (0xff - a)) / 0xff) << shift
+            };
+
+            0xff000000 | mix(16) | mix(8) | mix(0)
+        }
+    }
+}
+
+// src scaled by its alpha, added to dst
+#[inline(always)]
+pub fn add(dst: u32, src: u32) -> u32 {
+    let a = src >> 24;
+
+    let mix = |shift: u32| {
+        let s = (src >> shift) & 0xff;
+        let d = (dst >> shift) & 0xff;
+
+        (d + s * a / 0xff).min(0xff) << shift
+    };
+
+    0xff000000 | mix(16) | mix(8) | mix(0)
+}
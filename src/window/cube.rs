@@ -1,4 +1,4 @@
-//!
+//! Collision boxes.
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BoxAABB {
@@ -14,6 +14,15 @@ pub struct Point {
     y: u32,
 }
 
+// pushbox, hurtboxes, hitboxes and throw boxes of one frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Boxes<T> {
+    pub push: Option<T>,
+    pub hurt: Vec<T>,
+    pub hit: Vec<T>,
+    pub throw: Vec<T>,
+}
+
 ///////////////////////////////////////
 impl Point {
     pub fn new(x: u32, y: u32) -> Self {
@@ -22,7 +31,7 @@ impl Point {
 }
 
 impl BoxAABB {
-    fn new(x_min: u32, x_max: u32, y_min: u32, y_max: u32) -> Self {
+    pub fn new(x_min: u32, x_max: u32, y_min: u32, y_max: u32) -> Self {
         Self {
             x_min,
             x_max,
@@ -30,6 +39,28 @@ impl BoxAABB {
             y_max,
         }
     }
+
+    #[inline(always)]
+    pub fn overlap(&self, other: &BoxAABB) -> bool {
+        self.x_min < other.x_max
+            && other.x_min < self.x_max
+            && self.y_min < other.y_max
+            && other.y_min < self.y_max
+    }
+
+    // center of the overlap
+    pub fn contact(&self, other: &BoxAABB) -> (u32, u32) {
+        (
+            (self.x_min.max(other.x_min) + self.x_max.min(other.x_max)) / 2,
+            (self.y_min.max(other.y_min) + self.y_max.min(other.y_max)) / 2,
+        )
+    }
+}
+
+impl<T> Boxes<T> {
+    pub fn is_empty(&self) -> bool {
+        self.push.is_none() && self.hurt.is_empty() && self.hit.is_empty() && self.throw.is_empty()
+    }
 }
 
 ///////////////////////////////////////
@@ -44,4 +75,15 @@ impl Default for BoxAABB {
         Self::new(0, 0, 0, 0)
     }
 }
+
+impl<T> Default for Boxes<T> {
+    fn default() -> Self {
+        Self {
+            push: None,
+            hurt: vec![],
+            hit: vec![],
+            throw: vec![],
+        }
+    }
+}
 ///////////////////////////////////////
@@ -0,0 +1,256 @@
+//! Hit sparks, guard sparks, dust and the KO flash, on the render side only.
+
+use crate::{
+    game::{sim::Game, state::State},
+    res::{event::Event, sprite::argb_u32},
+    window::{
+        camera::Camera,
+        canvas::Blend,
+        render::{Draw, Layer, RenderQueue},
+    },
+};
+use asefile::AsepriteFile;
+use std::path::Path;
+
+// at the same time, the oldest goes first
+const LIMIT: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    HitSpark,
+    GuardSpark,
+    Dust,
+    KoFlash,
+}
+
+// all the frames of one .ase file
+#[derive(Debug, Clone)]
+pub struct Anim {
+    frames: Vec<Vec<u32>>,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Effect {
+    pub kind: Kind,
+    pub x: i32, // center, stage coordinates
+    pub y: i32, // center, height above the ground
+    pub age: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Effects {
+    anims: Vec<Option<Anim>>, // by Kind, None: a shape instead
+    pub list: Vec<Effect>,
+
+    prev: [(u32, u32); 2], // height and hp of the players, last tick
+}
+
+////////////////////////////////////////
+impl Kind {
+    pub fn all() -> [Kind; 4] {
+        [Kind::HitSpark, Kind::GuardSpark, Kind::Dust, Kind::KoFlash]
+    }
+
+    // {name}.ase
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::HitSpark => "hit_spark",
+            Kind::GuardSpark => "guard_spark",
+            Kind::Dust => "dust",
+            Kind::KoFlash => "ko_flash",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Kind::all().into_iter().find(|kind| kind.name() == name)
+    }
+
+    // in ticks
+    fn life(&self) -> u32 {
+        match self {
+            Kind::HitSpark => 16,
+            Kind::GuardSpark => 12,
+            Kind::Dust => 24,
+            Kind::KoFlash => 60,
+        }
+    }
+
+    fn blend(&self) -> Blend {
+        match self {
+            Kind::Dust => Blend::Alpha,
+            _ => Blend::Add,
+        }
+    }
+
+    // the shape without an animation: color, radius
+    fn shape(&self) -> (u32, u32) {
+        match self {
+            Kind::HitSpark => (0xfff0e060, 40),
+            Kind::GuardSpark => (0xff60a0f0, 32),
+            Kind::Dust => (0xffa09080, 28),
+            Kind::KoFlash => (0xffffffff, 0),
+        }
+    }
+}
+
+impl Anim {
+    // visible layers, merged
+    pub fn open(path: &Path) -> Self {
+        let ase = AsepriteFile::read_file(path).unwrap();
+        let mut frames = Vec::with_capacity(ase.num_frames() as usize);
+
+        for idx in 0..ase.num_frames() {
+            let mut data = vec![];
+            argb_u32(&mut data, ase.frame(idx).image().as_raw());
+            frames.push(data);
+        }
+
+        Self {
+            frames,
+            width: ase.width() as u32,
+            height: ase.height() as u32,
+        }
+    }
+}
+
+impl Effects {
+    // {dir}/{kind}.ase, if there is one
+    pub fn load(dir: &Path) -> Self {
+        let anims = Kind::all()
+            .iter()
+            .map(|kind| {
+                let path = dir.join(format!("{}.ase", kind.name()));
+                path.exists().then(|| Anim::open(&path))
+            })
+            .collect::<Vec<_>>();
+
+        log::debug!(
+            "Effects: {} of {} animations",
+            anims.iter().flatten().count(),
+            anims.len()
+        );
+
+        Self {
+            anims,
+            ..Self::default()
+        }
+    }
+
+    pub fn spawn(&mut self, kind: Kind, (x, y): (i32, i32)) {
+        if self.list.len() >= LIMIT {
+            self.list.remove(0);
+        }
+
+        self.list.push(Effect { kind, x, y, age: 0 });
+    }
+
+    // after Game::update(), from the hits and the players
+    pub fn observe(&mut self, game: &Game) {
+        for hit in game.hits.iter() {
+            let kind = if hit.blocked {
+                Kind::GuardSpark
+            } else {
+                Kind::HitSpark
+            };
+
+            self.spawn(kind, (hit.at.0 as i32, hit.at.1 as i32));
+        }
+
+        // at the feet
+        for (idx, event) in game.events.iter() {
+            let Event::Effect(name) = event else {
+                continue;
+            };
+
+            match Kind::parse(name) {
+                Some(kind) => {
+                    let p = game.players()[*idx];
+                    self.spawn(kind, (p.center() as i32, p.y_offset as i32));
+                }
+                None => log::warn!("Effects: no effect `{}`", name),
+            }
+        }
+
+        for (idx, p) in game.players().into_iter().enumerate() {
+            let (y, hp) = self.prev[idx];
+            let feet = (p.center() as i32, 0);
+
+            if (y > 0 && p.y_offset == 0) || (p.machine.is(State::Dash) && p.machine.timer == 0) {
+                self.spawn(Kind::Dust, feet);
+            }
+
+            if hp > 0 && p.hp == 0 {
+                self.spawn(Kind::KoFlash, (p.center() as i32, p.y_offset as i32));
+            }
+
+            self.prev[idx] = (p.y_offset, p.hp);
+        }
+    }
+
+    // every tick
+    pub fn update(&mut self) {
+        for e in self.list.iter_mut() {
+            e.age += 1;
+        }
+
+        self.list.retain(|e| e.age < e.kind.life());
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        queue: &mut RenderQueue<'a>,
+        camera: &Camera,
+        ground: u32,
+        (width, height): (u32, u32),
+    ) {
+        for e in self.list.iter() {
+            let life = e.kind.life();
+            let alpha = 0xff * (life - e.age) / life; // fades out
+            let x = e.x - camera.x;
+            let y = ground as i32 - e.y - camera.y;
+
+            // the whole screen
+            if e.kind == Kind::KoFlash {
+                let argb = (alpha / 2) << 24 | 0xffffff;
+                queue.push(
+                    Layer::Effect,
+                    10,
+                    0,
+                    0,
+                    Draw::Rect {
+                        width,
+                        height,
+                        argb,
+                    },
+                );
+            }
+
+            match self.anims.get(e.kind as usize).and_then(Option::as_ref) {
+                Some(anim) if !anim.frames.is_empty() => {
+                    let idx = (e.age * anim.frames.len() as u32 / life) as usize;
+                    let draw = Draw::Blended {
+                        data: &anim.frames[idx],
+                        width: anim.width,
+                        mode: e.kind.blend(),
+                        alpha,
+                    };
+                    let (w, h) = (anim.width as i32, anim.height as i32);
+
+                    queue.push(Layer::Effect, 0, x - w / 2, y - h / 2, draw);
+                }
+
+                _ => {
+                    let (color, size) = e.kind.shape();
+                    let r = size * (e.age + life) / (life * 2); // grows
+                    let argb = alpha << 24 | (color & 0xffffff);
+
+                    if r > 0 {
+                        queue.push(Layer::Effect, 0, x, y, Draw::Ellipse { rx: r, ry: r, argb });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! HUD: health bars, EP gauges, round timer, win markers and names.
+
+use crate::{
+    res::{
+        chara::Meter,
+        font::{Align, Font, TextStyle},
+        sprite::{Player, EP_MAX, HP_MAX},
+    },
+    window::render::{Draw, Layer, RenderQueue},
+};
+
+// ticks before the red segment starts to drain
+const RED_DELAY: u32 = 40;
+const RED_SPEED: u32 = 8;
+
+const HP_COLOR: u32 = 0xffe8c020;
+const RED_COLOR: u32 = 0xffc02020;
+const EP_COLOR: u32 = 0xff2080f0;
+const EP_FULL_COLOR: u32 = 0xff60c0ff;
+const BACK_COLOR: u32 = 0xff202020;
+const BORDER_COLOR: u32 = 0xff000000;
+const TEXT_COLOR: u32 = 0xffffffff;
+const WIN_COLOR: u32 = 0xfff0d040;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthBar {
+    pub max: u32,
+    pub value: u32,
+    pub red: u32, // delayed damage
+    pub delay: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeterBar {
+    pub max: u32,
+    pub value: u32,
+    pub levels: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hud {
+    pub width: u32,
+    pub height: u32,
+
+    pub hp: [HealthBar; 2],
+    pub ep: [MeterBar; 2],
+    pub names: [String; 2],
+
+    pub timer: Option<u32>, // None: no time limit
+    pub wins: [u32; 2],
+    pub rounds: u32, // rounds to win
+
+    pub font: Font,
+}
+
+////////////////////////////////////////
+impl HealthBar {
+    pub fn new(max: u32) -> Self {
+        Self {
+            max,
+            value: max,
+            red: max,
+            delay: 0,
+        }
+    }
+
+    pub fn update(&mut self, hp: u32) {
+        let hp = hp.min(self.max);
+
+        if hp < self.value {
+            self.delay = RED_DELAY;
+        }
+
+        self.value = hp;
+
+        if self.red <= self.value {
+            self.red = self.value;
+        } else if self.delay > 0 {
+            self.delay -= 1;
+        } else {
+            self.red = self.red.saturating_sub(RED_SPEED).max(self.value);
+        }
+    }
+
+    // P1 grows from the left, P2 grows from the right
+    fn render(
+        &self,
+        queue: &mut RenderQueue,
+        (x, y): (i32, i32),
+        (width, height): (u32, u32),
+        flip: bool,
+    ) {
+        let value = width * self.value / self.max.max(1);
+        let red = width * self.red / self.max.max(1);
+        let at = |len: u32| if flip { x + (width - len) as i32 } else { x };
+
+        rect(queue, 0, x - 2, y - 2, width + 4, height + 4, BORDER_COLOR);
+        rect(queue, 1, x, y, width, height, BACK_COLOR);
+        rect(queue, 2, at(red), y, red, height, RED_COLOR);
+        rect(queue, 3, at(value), y, value, height, HP_COLOR);
+    }
+}
+
+impl MeterBar {
+    pub fn new(max: u32, levels: u32) -> Self {
+        Self {
+            max,
+            value: 0,
+            levels: levels.max(1),
+        }
+    }
+
+    // max and levels of the character
+    pub fn update(&mut self, ep: u32, meter: &Meter) {
+        self.max = meter.max;
+        self.levels = meter.levels.max(1);
+        self.value = ep.min(self.max);
+    }
+
+    pub fn level(&self) -> u32 {
+        self.value * self.levels / self.max.max(1)
+    }
+
+    fn render<'a>(
+        &self,
+        queue: &mut RenderQueue<'a>,
+        font: &'a Font,
+        (x, y): (i32, i32),
+        (width, height): (u32, u32),
+        flip: bool,
+    ) {
+        let value = width * self.value / self.max.max(1);
+        let at = |len: u32| if flip { x + (width - len) as i32 } else { x };
+
+        // filled levels are brighter
+        let filled = width * (self.level() * self.max / self.levels) / self.max.max(1);
+
+        rect(queue, 0, x - 2, y - 2, width + 4, height + 4, BORDER_COLOR);
+        rect(queue, 1, x, y, width, height, BACK_COLOR);
+        rect(queue, 2, at(value), y, value, height, EP_COLOR);
+        rect(queue, 3, at(filled), y, filled, height, EP_FULL_COLOR);
+
+        // segments
+        for idx in 1..self.levels {
+            let sx = x + (width * idx / self.levels) as i32;
+            rect(queue, 4, sx - 1, y, 2, height, BORDER_COLOR);
+        }
+
+        // level number
+        let (tx, align) = if flip {
+            (x - 8, Align::Right)
+        } else {
+            (x + width as i32 + 8, Align::Left)
+        };
+        let style = TextStyle::new(TEXT_COLOR, 3).align(align);
+
+        font.render(
+            queue,
+            Layer::Hud,
+            5,
+            (tx, y),
+            self.level().to_string(),
+            style,
+        );
+    }
+}
+
+impl Hud {
+    pub fn new(width: u32, height: u32, names: [&str; 2]) -> Self {
+        Self {
+            width,
+            height,
+            hp: [HealthBar::new(HP_MAX); 2],
+            ep: [MeterBar::new(EP_MAX, 3); 2],
+            names: names.map(str::to_string),
+            timer: None,
+            wins: [0; 2],
+            rounds: 2,
+            font: Font::builtin(),
+        }
+    }
+
+    pub fn update(&mut self, p1: &Player, p2: &Player) {
+        self.hp[0].update(p1.hp);
+        self.hp[1].update(p2.hp);
+        self.ep[0].update(p1.ep, &p1.chara.meter);
+        self.ep[1].update(p2.ep, &p2.chara.meter);
+    }
+
+    pub fn render<'a>(&'a self, queue: &mut RenderQueue<'a>) {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let margin = 20;
+        let gap = 60; // timer
+        let bar_w = (w / 2 - margin - gap).max(0) as u32;
+
+        // health
+        self.hp[0].render(queue, (margin, 30), (bar_w, 24), false);
+        self.hp[1].render(queue, (w / 2 + gap, 30), (bar_w, 24), true);
+
+        // EP
+        let ep_w = self.width / 3;
+        let font = &self.font;
+        self.ep[0].render(queue, font, (margin, h - 40), (ep_w, 16), false);
+        self.ep[1].render(
+            queue,
+            font,
+            (w - margin - ep_w as i32, h - 40),
+            (ep_w, 16),
+            true,
+        );
+
+        // names
+        let style = TextStyle::new(TEXT_COLOR, 3).outline(BORDER_COLOR);
+        let names = &self.names;
+        font.render(queue, Layer::Hud, 5, (margin, 64), names[0].clone(), style);
+        let style = style.align(Align::Right);
+        font.render(
+            queue,
+            Layer::Hud,
+            5,
+            (w - margin, 64),
+            names[1].clone(),
+            style,
+        );
+
+        // timer
+        let text = match self.timer {
+            Some(t) => format!("{:02}", t),
+            None => "--".to_string(),
+        };
+        let style = TextStyle::new(TEXT_COLOR, 6)
+            .outline(BORDER_COLOR)
+            .align(Align::Center);
+        font.render(queue, Layer::Hud, 5, (w / 2, 24), text, style);
+
+        // win markers, next to the timer
+        for idx in 0..self.rounds {
+            let color = |won: bool| if won { WIN_COLOR } else { BACK_COLOR };
+            let offset = (idx * 16) as i32;
+
+            rect(
+                queue,
+                0,
+                w / 2 - gap - 14 - offset,
+                64,
+                10,
+                10,
+                color(idx < self.wins[0]),
+            );
+            rect(
+                queue,
+                0,
+                w / 2 + gap + 4 + offset,
+                64,
+                10,
+                10,
+                color(idx < self.wins[1]),
+            );
+        }
+    }
+}
+
+////////////////////////////////////////
+#[inline(always)]
+fn rect(queue: &mut RenderQueue, z: i32, x: i32, y: i32, width: u32, height: u32, argb: u32) {
+    queue.push(
+        Layer::Hud,
+        z,
+        x,
+        y,
+        Draw::Rect {
+            width,
+            height,
+            argb,
+        },
+    );
+}
@@ -0,0 +1,137 @@
+//! Debug overlay: pushboxes, hurtboxes, hitboxes, origins and stage bounds.
+
+use crate::{
+    game::entity::Entity,
+    res::{sprite::Player, stage::Stage},
+    window::{
+        camera::Camera,
+        cube::BoxAABB,
+        render::{Draw, Layer, RenderQueue},
+    },
+};
+use minifb::{Key, KeyRepeat, Window};
+
+const PUSH_COLOR: u32 = 0x50f0f0f0;
+const HURT_COLOR: u32 = 0x5020d020;
+const HIT_COLOR: u32 = 0x60f02020;
+const THROW_COLOR: u32 = 0x60f0a0f0;
+const ORIGIN_COLOR: u32 = 0xffffffff;
+const BOUNDS_COLOR: u32 = 0xc0f0a020;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overlay {
+    pub enabled: bool,
+}
+
+////////////////////////////////////////
+impl Overlay {
+    pub fn new() -> Self {
+        Self { enabled: false }
+    }
+
+    // F1
+    pub fn check_keys(&mut self, window: &Window) {
+        if window.is_key_pressed(Key::F1, KeyRepeat::No) {
+            self.enabled = !self.enabled;
+        }
+    }
+
+    pub fn render(
+        &self,
+        queue: &mut RenderQueue,
+        camera: &Camera,
+        stage: &Stage,
+        players: &[&Player],
+        entities: &[Entity],
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        // stage coordinates -> screen
+        let ground = stage.ground as i32 - camera.y;
+        let to_screen = |b: &BoxAABB| {
+            (
+                b.x_min as i32 - camera.x,
+                ground - b.y_max as i32,
+                b.x_max - b.x_min,
+                b.y_max - b.y_min,
+            )
+        };
+
+        for b in entities.iter().map(Entity::hitbox) {
+            let (x, y, w, h) = to_screen(&b);
+
+            fill(queue, 2, (x, y), (w, h), HIT_COLOR);
+            frame(queue, 2, (x, y), (w, h), HIT_COLOR | 0xff000000);
+        }
+
+        for player in players.iter() {
+            let boxes = player.hitboxes();
+
+            for (z, list, color) in [
+                (0, boxes.push.iter().collect::<Vec<_>>(), PUSH_COLOR),
+                (1, boxes.hurt.iter().collect(), HURT_COLOR),
+                (2, boxes.hit.iter().collect(), HIT_COLOR),
+                (2, boxes.throw.iter().collect(), THROW_COLOR),
+            ] {
+                for b in list {
+                    let (x, y, w, h) = to_screen(b);
+
+                    fill(queue, z, (x, y), (w, h), color);
+                    frame(queue, z, (x, y), (w, h), color | 0xff000000);
+                }
+            }
+
+            // origin
+            let x = player.center() as i32 - camera.x;
+            let y = ground - player.y_offset as i32;
+
+            fill(queue, 3, (x - 8, y), (17, 1), ORIGIN_COLOR);
+            fill(queue, 3, (x, y - 8), (1, 17), ORIGIN_COLOR);
+        }
+
+        // stage bounds and ground
+        let (x, y) = (-camera.x, -camera.y);
+
+        frame(queue, 3, (x, y), (stage.width, stage.height), BOUNDS_COLOR);
+        fill(queue, 3, (x, ground), (stage.width, 1), BOUNDS_COLOR);
+    }
+}
+
+////////////////////////////////////////
+#[inline(always)]
+fn fill(
+    queue: &mut RenderQueue,
+    z: i32,
+    (x, y): (i32, i32),
+    (width, height): (u32, u32),
+    argb: u32,
+) {
+    queue.push(
+        Layer::Debug,
+        z,
+        x,
+        y,
+        Draw::Rect {
+            width,
+            height,
+            argb,
+        },
+    );
+}
+
+fn frame(
+    queue: &mut RenderQueue,
+    z: i32,
+    (x, y): (i32, i32),
+    (width, height): (u32, u32),
+    argb: u32,
+) {
+    let (w, h) = (width as i32, height as i32);
+
+    fill(queue, z, (x, y), (width, 1), argb);
+    fill(queue, z, (x, y + h - 1), (width, 1), argb);
+    fill(queue, z, (x, y), (1, height), argb);
+    fill(queue, z, (x + w - 1, y), (1, height), argb);
+}
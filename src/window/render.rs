@@ -0,0 +1,135 @@
+//! Render queue, sorted by layer and z before drawing.
+
+use crate::window::canvas::{Blend, Canvas};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    Stage = 0,
+    Shadow = 1,
+    Player = 2,
+    Effect = 3,
+    Hud = 4,
+    Debug = 5,
+}
+
+pub enum Draw<'a> {
+    Sprite {
+        data: &'a [u32],
+        width: u32,
+    },
+    Blended {
+        data: &'a [u32],
+        width: u32,
+        mode: Blend,
+        alpha: u32,
+    },
+    // a part of an atlas page
+    Region {
+        data: &'a [u32],
+        stride: u32,
+        rect: (u32, u32, u32, u32), // x, y, width, height in the page
+        flip: bool,
+    },
+    Rect {
+        width: u32,
+        height: u32,
+        argb: u32,
+    },
+    Ellipse {
+        rx: u32,
+        ry: u32,
+        argb: u32,
+    },
+    Custom(Box<dyn Fn(&mut Canvas) + 'a>),
+}
+
+pub struct Item<'a> {
+    pub layer: Layer,
+    pub z: i32,
+    pub x: i32,
+    pub y: i32,
+    pub draw: Draw<'a>,
+}
+
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    items: Vec<Item<'a>>,
+}
+
+////////////////////////////////////////
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, layer: Layer, z: i32, x: i32, y: i32, draw: Draw<'a>) {
+        self.items.push(Item {
+            layer,
+            z,
+            x,
+            y,
+            draw,
+        });
+    }
+
+    #[inline(always)]
+    pub fn sprite(&mut self, layer: Layer, z: i32, x: i32, y: i32, data: &'a [u32], width: u32) {
+        self.push(layer, z, x, y, Draw::Sprite { data, width });
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // same layer and z: first pushed, first drawn
+    pub fn flush(&mut self, canvas: &mut Canvas) {
+        self.items.sort_by_key(|item| (item.layer, item.z));
+
+        for item in self.items.drain(..) {
+            match item.draw {
+                Draw::Sprite { data, width } => {
+                    canvas.blit(item.x, item.y, data, width);
+                }
+
+                Draw::Blended {
+                    data,
+                    width,
+                    mode,
+                    alpha,
+                } => {
+                    canvas.blit_blend(item.x, item.y, data, width, mode, alpha);
+                }
+
+                Draw::Region {
+                    data,
+                    stride,
+                    rect,
+                    flip,
+                } => {
+                    canvas.blit_region(item.x, item.y, data, stride, rect, flip);
+                }
+
+                Draw::Rect {
+                    width,
+                    height,
+                    argb,
+                } => {
+                    canvas.fill_rect(item.x, item.y, width, height, argb);
+                }
+
+                Draw::Ellipse { rx, ry, argb } => {
+                    canvas.fill_ellipse(item.x, item.y, rx, ry, argb);
+                }
+
+                Draw::Custom(f) => {
+                    f(canvas);
+                }
+            }
+        }
+    }
+}